@@ -0,0 +1,34 @@
+//! Times `FileSystem` lookups by ID and by name over 10,000 files.
+//!
+//! Run with `cargo run --release --example index_benchmark`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use cli::file::FileSystem;
+
+const FILES: u32 = 10_000;
+
+fn main() {
+    let mut fs = FileSystem::with_capacity(FILES as usize);
+    for i in 0..FILES {
+        fs.create_file(format!("file_{}.txt", i), format!("content {}", i)).unwrap();
+    }
+
+    let start = Instant::now();
+    for id in 1..=FILES {
+        black_box(&fs[id]);
+    }
+    report("fs[id]", start);
+
+    let start = Instant::now();
+    for i in 0..FILES {
+        black_box(&fs[format!("file_{}.txt", i).as_str()]);
+    }
+    report("fs[name]", start);
+}
+
+fn report(label: &str, start: Instant) {
+    let elapsed = start.elapsed();
+    println!("{:<10} {:>10.2?} total, {:>8.0} ns per lookup", label, elapsed, elapsed.as_nanos() as f64 / FILES as f64);
+}
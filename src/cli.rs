@@ -1,83 +1,860 @@
 use crate::error::{FileError, FileResult};
-use crate::file::{FileSystem, FileDisplay};
-use std::io::{self, Write};
+use crate::file::{FileDisplay, FilePermissions, FileSystem, FileWatcher, SortField, SortOrder};
+use crate::pipeline::TransformStep;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+/// ANSI styling for CLI output. Every method returns its input unchanged while
+/// color is disabled.
+#[derive(Debug, Clone, Copy, Default)]
+struct Palette {
+    enabled: bool,
+}
+
+impl Palette {
+    fn paint(self, code: &str, text: &str) -> String {
+        if self.enabled {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn green(self, text: &str) -> String {
+        self.paint("32", text)
+    }
+
+    fn red(self, text: &str) -> String {
+        self.paint("31", text)
+    }
+
+    fn yellow(self, text: &str) -> String {
+        self.paint("33", text)
+    }
+
+    fn bold(self, text: &str) -> String {
+        self.paint("1", text)
+    }
+}
+
+/// Raw terminal input for prompts that complete on Tab
+#[cfg(unix)]
+mod terminal {
+    use std::io;
+
+    /// Turns off line buffering and echo on stdin until dropped, so keys arrive one
+    /// at a time. Signals such as Ctrl-C keep working.
+    pub struct RawMode {
+        original: libc::termios,
+        raw: libc::termios,
+    }
+
+    impl RawMode {
+        pub fn enable() -> io::Result<Self> {
+            // SAFETY: termios is plain old data, and tcgetattr fills it in before use
+            let mut original: libc::termios = unsafe { std::mem::zeroed() };
+            if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            set_attributes(&raw)?;
+            Ok(RawMode { original, raw })
+        }
+
+        /// Runs `read` with reads from stdin returning 0 bytes after a tenth of a second
+        /// without input, instead of blocking until a key is pressed
+        pub fn with_timeout<T>(&self, read: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+            let mut timed = self.raw;
+            timed.c_cc[libc::VMIN] = 0;
+            timed.c_cc[libc::VTIME] = 1;
+            set_attributes(&timed)?;
+            let result = read();
+            set_attributes(&self.raw)?;
+            result
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            // Nothing can be done about a failure while dropping
+            let _ = set_attributes(&self.original);
+        }
+    }
+
+    /// Applies terminal attributes to stdin immediately
+    fn set_attributes(attributes: &libc::termios) -> io::Result<()> {
+        // SAFETY: `attributes` is a valid, initialized termios that outlives the call,
+        // and tcsetattr only reads it
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, attributes) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// The longest prefix shared by `a` and `b`
+    pub fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+        let end = a
+            .char_indices()
+            .zip(b.chars())
+            .find(|((_, x), y)| x != y)
+            .map_or_else(|| a.len().min(b.len()), |((i, _), _)| i);
+        &a[..end]
+    }
+}
+
+/// Which files the `list` command shows
+#[derive(Debug, Clone)]
+pub enum ListFilter {
+    All,
+    Extension(String),
+    NoExtension,
+    Size { min: usize, max: usize },
+}
+
+impl ListFilter {
+    /// Parses the arguments following `list`, e.g. `--ext txt`, `.txt`, `--no-ext`
+    /// or `--min 10 --max 100`
+    fn parse(args: &str) -> FileResult<Self> {
+        let usage = || FileError::InvalidInput(
+            "Usage: list [--ext <ext> | .<ext> | --no-ext | --min <bytes> --max <bytes>]".to_string(),
+        );
+
+        let tokens: Vec<&str> = args.split_whitespace().collect();
+        match tokens.as_slice() {
+            [] => Ok(ListFilter::All),
+            ["--no-ext"] => Ok(ListFilter::NoExtension),
+            ["--ext", ext] => Ok(ListFilter::Extension(ext.trim_start_matches('.').to_string())),
+            [ext] if ext.starts_with('.') && ext.len() > 1 => {
+                Ok(ListFilter::Extension(ext[1..].to_string()))
+            }
+            [first, ..] if first.starts_with("--m") => {
+                let mut min = 0;
+                let mut max = usize::MAX;
+                for pair in tokens.chunks(2) {
+                    let [flag, value] = pair else { return Err(usage()) };
+                    let value = value.parse::<usize>().map_err(|_| usage())?;
+                    match *flag {
+                        "--min" => min = value,
+                        "--max" => max = value,
+                        _ => return Err(usage()),
+                    }
+                }
+                Ok(ListFilter::Size { min, max })
+            }
+            _ => Err(usage()),
+        }
+    }
+}
+
+/// Parses the arguments following `sort`, e.g. `size desc`; defaults to `name asc`
+fn parse_sort_args(args: &str) -> FileResult<(SortField, SortOrder)> {
+    let usage = || FileError::InvalidInput(
+        "Usage: sort [name|size|id|created] [asc|desc]".to_string(),
+    );
+
+    let mut field = SortField::Name;
+    let mut order = SortOrder::Asc;
+    let tokens: Vec<String> = args.split_whitespace().map(str::to_lowercase).collect();
+    if tokens.len() > 2 {
+        return Err(usage());
+    }
+    for token in &tokens {
+        match token.as_str() {
+            "name" => field = SortField::Name,
+            "size" => field = SortField::Size,
+            "id" => field = SortField::Id,
+            "created" => field = SortField::Created,
+            "asc" => order = SortOrder::Asc,
+            "desc" => order = SortOrder::Desc,
+            _ => return Err(usage()),
+        }
+    }
+    Ok((field, order))
+}
+
+/// Parses the arguments following `set`: `case-insensitive`, `case-sensitive`,
+/// `confirm on`, `confirm off` or `pagesize <lines>`
+fn parse_set_args(args: &str) -> FileResult<Operation> {
+    let usage = || FileError::InvalidInput(
+        "Usage: set [case-insensitive|case-sensitive|confirm on|confirm off|pagesize <lines>]".to_string(),
+    );
+
+    let tokens: Vec<String> = args.split_whitespace().map(str::to_lowercase).collect();
+    match tokens.as_slice() {
+        [setting] if setting == "case-insensitive" => Ok(Operation::CaseInsensitive(true)),
+        [setting] if setting == "case-sensitive" => Ok(Operation::CaseInsensitive(false)),
+        [setting, value] if setting == "confirm" && value == "on" => Ok(Operation::Confirm(true)),
+        [setting, value] if setting == "confirm" && value == "off" => Ok(Operation::Confirm(false)),
+        [setting, value] if setting == "pagesize" => value.parse().map(Operation::PageSize).map_err(|_| usage()),
+        _ => Err(usage()),
+    }
+}
+
+/// Parses the arguments following `top`: a count, then optionally `largest` or `smallest`
+fn parse_top_args(args: &str) -> FileResult<Operation> {
+    let usage = || FileError::InvalidInput("Usage: top <count> [largest|smallest]".to_string());
+
+    let tokens: Vec<String> = args.split_whitespace().map(str::to_lowercase).collect();
+    let (count, largest) = match tokens.as_slice() {
+        [count] => (count, true),
+        [count, direction] if direction == "largest" => (count, true),
+        [count, direction] if direction == "smallest" => (count, false),
+        _ => return Err(usage()),
+    };
+    let count = count.parse::<usize>().map_err(|_| usage())?;
+    Ok(Operation::Top(count, largest))
+}
+
+/// Inline flags given to a command, with their values if they take one
+type Flags = HashMap<String, Option<String>>;
+
+/// Short spellings accepted for long flags
+const FLAG_ALIASES: [(&str, &str); 3] = [("-i", "--ignore-case"), ("-r", "--recursive"), ("-C", "--context")];
+
+/// The inline arguments a command accepts. Flags may appear anywhere among the
+/// positional arguments, and `--` ends the flags so the words after it are positional.
+#[derive(Debug, Clone, Copy)]
+struct ArgSpec {
+    /// How many positional arguments answer the command's prompts
+    positional: usize,
+    /// Whether any number of further positional arguments form a list
+    variadic: bool,
+    /// Flags that take no value, e.g. `--force`
+    switches: &'static [&'static str],
+    /// Flags followed by a value, e.g. `--col 0`
+    options: &'static [&'static str],
+}
+
+impl ArgSpec {
+    const fn positional(count: usize) -> Self {
+        ArgSpec { positional: count, variadic: false, switches: &[], options: &[] }
+    }
+
+    const fn variadic(fixed: usize) -> Self {
+        ArgSpec { positional: fixed, variadic: true, switches: &[], options: &[] }
+    }
+
+    /// Separates flags from positional arguments, rejecting flags the command doesn't
+    /// know and more positional arguments than it has prompts for
+    fn parse(&self, args: Vec<String>) -> FileResult<(VecDeque<String>, Flags)> {
+        let mut positional = VecDeque::new();
+        let mut flags = HashMap::new();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                positional.extend(args.by_ref());
+                break;
+            }
+            let flag = FLAG_ALIASES
+                .iter()
+                .find(|(short, _)| *short == arg)
+                .map_or(arg.as_str(), |(_, long)| long);
+
+            if self.switches.contains(&flag) {
+                flags.insert(flag.to_string(), None);
+            } else if self.options.contains(&flag) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| FileError::InvalidInput(format!("Option '{}' needs a value", flag)))?;
+                flags.insert(flag.to_string(), Some(value));
+            } else if arg.starts_with("--") && arg.len() > 2 {
+                return Err(FileError::InvalidInput(format!("Unknown option '{}'", arg)));
+            } else {
+                positional.push_back(arg);
+            }
+        }
+
+        if !self.variadic && positional.len() > self.positional {
+            let extra: Vec<&str> = positional.iter().skip(self.positional).map(String::as_str).collect();
+            return Err(FileError::InvalidInput(format!(
+                "Unexpected extra argument{} '{}' (use quotes for words that belong together)",
+                if extra.len() == 1 { "" } else { "s" },
+                extra.join(" ")
+            )));
+        }
+        Ok((positional, flags))
+    }
+}
+
+/// Splits command arguments on whitespace with shell-like quoting. Double quotes group
+/// words and understand `\n`, `\t`, `\"` and `\\`; single quotes group words literally;
+/// outside quotes a backslash escapes the next character.
+fn split_args(args: &str) -> FileResult<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = args.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(token) = current.take() {
+                    tokens.push(token);
+                }
+            }
+            '"' => {
+                let token = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => token.push('\n'),
+                            Some('t') => token.push('\t'),
+                            Some(c @ ('"' | '\\')) => token.push(c),
+                            Some(c) => {
+                                token.push('\\');
+                                token.push(c);
+                            }
+                            None => return Err(FileError::InvalidInput("Unterminated double quote".to_string())),
+                        },
+                        Some(c) => token.push(c),
+                        None => return Err(FileError::InvalidInput("Unterminated double quote".to_string())),
+                    }
+                }
+            }
+            '\'' => {
+                let token = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => token.push(c),
+                        None => return Err(FileError::InvalidInput("Unterminated single quote".to_string())),
+                    }
+                }
+            }
+            '\\' => {
+                let token = current.get_or_insert_with(String::new);
+                if let Some(c) = chars.next() {
+                    token.push(c);
+                }
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    tokens.extend(current);
+    Ok(tokens)
+}
+
+/// Writes `items` one per line, pausing after every `page_size` lines until Enter is
+/// pressed; `q` or the end of input stops early. A `page_size` of 0 disables paging.
+fn paginate(items: &[String], page_size: usize, input: &mut impl BufRead, output: &mut impl Write) -> FileResult<()> {
+    let page_size = if page_size == 0 { items.len().max(1) } else { page_size };
+    let mut pages = items.chunks(page_size).peekable();
+
+    while let Some(page) = pages.next() {
+        for item in page {
+            writeln!(output, "{}", item)?;
+        }
+        if pages.peek().is_none() {
+            break;
+        }
+
+        write!(output, "-- More -- [Enter to continue, q to quit]")?;
+        output.flush()?;
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 || answer.trim().eq_ignore_ascii_case("q") {
+            break;
+        }
+    }
+    Ok(())
+}
 
 /// CLI operations enum
 #[derive(Debug, Clone)]
 pub enum Operation {
     Create,
     Write,
+    Append,
     Read,
-    List,
+    ReadLines,
+    List(ListFilter),
     Delete,
+    Truncate,
+    Replace,
+    ReplaceAll,
+    Copy,
+    Concat,
+    Split,
+    Move,
     Info,
+    Search,
+    Glob,
+    Recent,
+    Sort(SortField, SortOrder),
     Help,
+    History,
     Stats,
+    StatsJson,
+    CsvSort,
+    Lint,
+    CommonPrefix,
+    CommonSuffix,
+    BatchRename,
+    ZipRead,
+    SearchContext,
+    Readability,
+    Pad,
+    Symlink,
+    Hardlink,
+    Pipeline,
+    ExportRss,
+    ImportRss,
+    ExportJson,
+    ImportJson,
+    ExportDir,
+    ImportDir,
+    IdReport,
+    AutoTag,
+    AutoTagAll,
+    Xref,
+    Average,
+    GrepCollect,
+    Grep,
+    Watch,
+    MakeIndex,
+    Tag,
+    Untag,
+    Lock,
+    Unlock,
+    ReadOnly,
+    ReadWrite,
+    Chmod,
+    Dupes,
+    Diff,
+    Versions,
+    Revert,
+    Mkdir,
+    Rmdir,
+    Cd,
+    Pwd,
+    CaseInsensitive(bool),
+    Confirm(bool),
+    PageSize(usize),
+    /// Show the `n` largest (`true`) or smallest (`false`) files
+    Top(usize, bool),
     Quit,
 }
 
 impl Operation {
+    /// Parses a command line into an Operation and the arguments that answer its prompts
+    /// in order, e.g. `create notes.txt "line one\nline two"`. Commands with their own
+    /// options (`list`, `sort`, `set`, `top`, `stats --json`) take no prompt arguments.
+    pub fn parse(input: &str) -> FileResult<(Self, Vec<String>)> {
+        let input = input.trim();
+        let (command, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+        Self::parse_args(command, split_args(args)?)
+    }
+
+    /// Like `parse`, for a command whose arguments have already been split
+    pub fn parse_args(command: &str, args: Vec<String>) -> FileResult<(Self, Vec<String>)> {
+        match command.to_lowercase().as_str() {
+            "list" | "l" | "ls" | "sort" | "set" | "top" => {
+                Ok((Self::from_str(&format!("{} {}", command, args.join(" ")))?, Vec::new()))
+            }
+            "stats" if args == ["--json"] => Ok((Operation::StatsJson, Vec::new())),
+            _ => Ok((Self::from_str(command)?, args)),
+        }
+    }
+
+    /// The inline arguments the operation accepts
+    fn arg_spec(&self) -> ArgSpec {
+        match self {
+            Operation::Read | Operation::Info | Operation::Glob | Operation::Recent | Operation::Readability
+            | Operation::ImportRss | Operation::ImportJson | Operation::ExportDir | Operation::ImportDir
+            | Operation::AutoTag | Operation::Xref | Operation::Watch | Operation::MakeIndex | Operation::Lock
+            | Operation::Unlock | Operation::Versions | Operation::Mkdir | Operation::Cd => ArgSpec::positional(1),
+            Operation::Create | Operation::Write | Operation::Append | Operation::ReplaceAll | Operation::Copy
+            | Operation::Move | Operation::BatchRename | Operation::ZipRead | Operation::Symlink
+            | Operation::Hardlink | Operation::Grep | Operation::Tag | Operation::Untag | Operation::Chmod
+            | Operation::Diff | Operation::Revert => ArgSpec::positional(2),
+            Operation::ReadLines | Operation::Split => ArgSpec::positional(3),
+            Operation::Concat => ArgSpec::positional(4),
+            Operation::Delete | Operation::Truncate => ArgSpec { switches: &["--force"], ..ArgSpec::positional(1) },
+            Operation::Replace => ArgSpec { switches: &["--all"], ..ArgSpec::positional(3) },
+            Operation::Search => ArgSpec { switches: &["--ignore-case"], ..ArgSpec::positional(1) },
+            Operation::CsvSort => ArgSpec {
+                switches: &["--numeric", "--desc"],
+                options: &["--col"],
+                ..ArgSpec::positional(2)
+            },
+            Operation::CommonPrefix | Operation::CommonSuffix | Operation::Average => ArgSpec::variadic(0),
+            Operation::Pipeline => ArgSpec::variadic(1),
+            Operation::SearchContext => ArgSpec { options: &["--context"], ..ArgSpec::positional(3) },
+            Operation::Pad => ArgSpec { options: &["--char"], ..ArgSpec::positional(3) },
+            Operation::ExportRss => ArgSpec {
+                options: &["--title", "--link", "--description"],
+                ..ArgSpec::positional(3)
+            },
+            Operation::GrepCollect => ArgSpec { switches: &["--case-sensitive"], ..ArgSpec::positional(2) },
+            Operation::Rmdir => ArgSpec { switches: &["--recursive"], ..ArgSpec::positional(1) },
+            Operation::Lint => ArgSpec { switches: &["--fix"], ..ArgSpec::positional(0) },
+            _ => ArgSpec::positional(0),
+        }
+    }
+
     /// Parses a command string into an Operation
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(input: &str) -> FileResult<Self> {
-        match input.trim().to_lowercase().as_str() {
+        let input = input.trim();
+        if let Some((command, args)) = input.split_once(char::is_whitespace) {
+            match command.to_lowercase().as_str() {
+                "list" | "l" | "ls" => return ListFilter::parse(args).map(Operation::List),
+                "sort" => return parse_sort_args(args).map(|(by, order)| Operation::Sort(by, order)),
+                "set" => return parse_set_args(args),
+                "top" => return parse_top_args(args),
+                _ => {}
+            }
+        }
+
+        match input.to_lowercase().as_str() {
             "create" | "c" => Ok(Operation::Create),
             "write" | "w" => Ok(Operation::Write),
+            "append" | "a" => Ok(Operation::Append),
             "read" | "r" => Ok(Operation::Read),
-            "list" | "l" | "ls" => Ok(Operation::List),
+            "readlines" => Ok(Operation::ReadLines),
+            "list" | "l" | "ls" => Ok(Operation::List(ListFilter::All)),
             "delete" | "d" | "del" => Ok(Operation::Delete),
+            "truncate" | "trunc" => Ok(Operation::Truncate),
+            "replace" => Ok(Operation::Replace),
+            "replace-all" => Ok(Operation::ReplaceAll),
+            "copy" | "cp" => Ok(Operation::Copy),
+            "concat" => Ok(Operation::Concat),
+            "split" => Ok(Operation::Split),
+            "move" | "mv" | "rename" => Ok(Operation::Move),
             "info" | "i" => Ok(Operation::Info),
+            "search" | "find" => Ok(Operation::Search),
+            "glob" => Ok(Operation::Glob),
+            "recent" => Ok(Operation::Recent),
+            "sort" => Ok(Operation::Sort(SortField::Name, SortOrder::Asc)),
             "help" | "h" | "?" => Ok(Operation::Help),
+            "history" => Ok(Operation::History),
             "stats" | "s" => Ok(Operation::Stats),
+            "stats --json" | "stats-json" => Ok(Operation::StatsJson),
+            "csv-sort" => Ok(Operation::CsvSort),
+            "lint" => Ok(Operation::Lint),
+            "common-prefix" => Ok(Operation::CommonPrefix),
+            "common-suffix" => Ok(Operation::CommonSuffix),
+            "batch-rename" => Ok(Operation::BatchRename),
+            "zip-read" => Ok(Operation::ZipRead),
+            "search-context" => Ok(Operation::SearchContext),
+            "readability" => Ok(Operation::Readability),
+            "pad" => Ok(Operation::Pad),
+            "symlink" | "ln" => Ok(Operation::Symlink),
+            "hardlink" => Ok(Operation::Hardlink),
+            "pipeline" => Ok(Operation::Pipeline),
+            "export-rss" => Ok(Operation::ExportRss),
+            "import-rss" => Ok(Operation::ImportRss),
+            "export" => Ok(Operation::ExportJson),
+            "import" => Ok(Operation::ImportJson),
+            "export-dir" => Ok(Operation::ExportDir),
+            "import-dir" => Ok(Operation::ImportDir),
+            "id-report" => Ok(Operation::IdReport),
+            "auto-tag" => Ok(Operation::AutoTag),
+            "auto-tag-all" => Ok(Operation::AutoTagAll),
+            "xref" => Ok(Operation::Xref),
+            "average" => Ok(Operation::Average),
+            "grep-collect" => Ok(Operation::GrepCollect),
+            "grep" => Ok(Operation::Grep),
+            "watch" => Ok(Operation::Watch),
+            "make-index" => Ok(Operation::MakeIndex),
+            "tag" => Ok(Operation::Tag),
+            "untag" => Ok(Operation::Untag),
+            "lock" => Ok(Operation::Lock),
+            "unlock" => Ok(Operation::Unlock),
+            "readonly" => Ok(Operation::ReadOnly),
+            "readwrite" => Ok(Operation::ReadWrite),
+            "chmod" => Ok(Operation::Chmod),
+            "dupes" => Ok(Operation::Dupes),
+            "diff" => Ok(Operation::Diff),
+            "versions" => Ok(Operation::Versions),
+            "revert" => Ok(Operation::Revert),
+            "mkdir" => Ok(Operation::Mkdir),
+            "rmdir" => Ok(Operation::Rmdir),
+            "cd" => Ok(Operation::Cd),
+            "pwd" => Ok(Operation::Pwd),
+            "top" => parse_top_args(""),
             "quit" | "q" | "exit" => Ok(Operation::Quit),
             _ => Err(FileError::InvalidInput(format!("Unknown command: {}", input))),
         }
     }
 }
 
-/// CLI interface for the file management system
-pub struct CLI {
+/// CLI interface for the file management system. `'io` is the lifetime of the
+/// reader and writer passed to `run_with_reader`.
+#[allow(clippy::upper_case_acronyms)]
+pub struct CLI<'io> {
     filesystem: FileSystem,
+    watchers: Vec<FileWatcher>,
+    /// Whether `read`, `info` and `delete` fall back to case-insensitive name matching
+    case_insensitive: bool,
+    /// Where commands and prompt answers are read from
+    input: Box<dyn BufRead + 'io>,
+    /// Where all output is written
+    output: Box<dyn Write + 'io>,
+    /// Inline arguments from the current command line, used in place of prompting
+    pending_args: VecDeque<String>,
+    /// Inline flags from the current command line
+    pending_flags: Flags,
+    /// Whether the current command was given any inline arguments, in which case
+    /// questions answered by flags and optional prompts aren't asked
+    inline: bool,
+    /// The last error a command reported itself rather than returning
+    last_error: Option<FileError>,
+    /// Whether `delete` and `truncate` ask before going ahead
+    confirm: bool,
+    /// Lines of `list` output shown before pausing; 0 shows everything at once
+    page_size: usize,
+    /// Commands entered at the prompt, oldest first
+    history: Vec<String>,
+    /// Whether file name prompts complete on Tab, which needs stdin to be a terminal
+    tab_completion: bool,
+    /// Styling for the current output
+    color: Palette,
+    /// Color choice made with `set_color`, which overrides the default for every writer
+    color_setting: Option<bool>,
 }
 
-impl CLI {
+impl<'io> CLI<'io> {
+    /// Creates a CLI on stdin and stdout. Output is colored if stdout is a terminal
+    /// and the `NO_COLOR` environment variable is unset or empty; output sent to any
+    /// other writer is plain unless `set_color` turns color on.
     pub fn new() -> Self {
+        let color = Palette {
+            enabled: io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+        };
         CLI {
             filesystem: FileSystem::new(),
+            watchers: Vec::new(),
+            case_insensitive: false,
+            input: Box::new(BufReader::new(io::stdin())),
+            output: Box::new(io::stdout()),
+            pending_args: VecDeque::new(),
+            pending_flags: HashMap::new(),
+            inline: false,
+            last_error: None,
+            confirm: true,
+            page_size: 20,
+            history: Vec::new(),
+            tab_completion: false,
+            color,
+            color_setting: None,
         }
     }
 
-    /// Starts the CLI loop
+    /// Turns colored output on or off, whatever the output is written to
+    pub fn set_color(&mut self, enabled: bool) {
+        self.color_setting = Some(enabled);
+        self.color = Palette { enabled };
+    }
+
+    /// Sends all further output to `writer` instead of stdout
+    pub fn set_output(&mut self, writer: Box<dyn Write + 'io>) {
+        self.output = writer;
+        self.color = Palette { enabled: self.color_setting.unwrap_or(false) };
+    }
+
+    /// Starts the CLI loop on stdin, writing to stdout or the writer given to `set_output`
     pub fn run(&mut self) -> FileResult<()> {
-        println!("🗂️  Welcome to the In-Memory File Management System!");
-        println!("Type 'help' to see available commands.\n");
+        let history_file = history_file();
+        if let Some(contents) = history_file.as_ref().and_then(|path| std::fs::read_to_string(path).ok()) {
+            self.history = contents.lines().map(str::to_string).collect();
+        }
+
+        self.tab_completion = io::stdin().is_terminal();
+        self.input = Box::new(BufReader::new(io::stdin()));
+        let result = self.run_loop();
+        self.tab_completion = false;
+
+        // History is a convenience, so failing to save it isn't worth reporting
+        if let Some(path) = history_file {
+            let _ = std::fs::write(path, self.history.iter().map(|line| format!("{}\n", line)).collect::<String>());
+        }
+        result
+    }
+
+    /// Runs the CLI loop, reading commands and prompt answers from `reader` and writing
+    /// all output to `writer`, until `quit` or the end of input
+    pub fn run_with_reader(&mut self, reader: impl BufRead + 'io, writer: impl Write + 'io) -> FileResult<()> {
+        self.input = Box::new(reader);
+        self.set_output(Box::new(writer));
+        self.run_loop()
+    }
+
+    /// Reads and runs commands until `quit` or the end of input
+    fn run_loop(&mut self) -> FileResult<()> {
+        writeln!(self.output, "🗂️  Welcome to the In-Memory File Management System!")?;
+        writeln!(self.output, "Type 'help' to see available commands.\n")?;
 
         loop {
-            print!("file-cli> ");
-            io::stdout().flush().unwrap();
+            write!(self.output, "file-cli> ")?;
+            self.output.flush()?;
 
             let mut input = String::new();
-            io::stdin().read_line(&mut input)
-                .map_err(|e| FileError::InvalidInput(format!("Failed to read input: {}", e)))?;
+            if self.input.read_line(&mut input)? == 0 {
+                writeln!(self.output)?;
+                break;
+            }
 
-            let operation = match Operation::from_str(&input) {
-                Ok(op) => op,
+            let line = match self.recall_history(input.trim()) {
+                Ok(line) => line,
                 Err(e) => {
-                    println!("❌ {}", e);
+                    self.report_error(&e)?;
                     continue;
                 }
             };
+            if !line.is_empty() {
+                self.history.push(line.clone());
+            }
 
-            match self.execute_operation(operation) {
-                Ok(should_continue) => {
-                    if !should_continue {
-                        break;
-                    }
+            match self.execute_command(&line) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => self.report_error(&e)?,
+            }
+        }
+
+        writeln!(self.output, "👋 Goodbye!")?;
+        Ok(())
+    }
+
+    /// Expands `!!` to the previous command and `!n` to command `n` of `history`,
+    /// echoing the recalled command. Other lines are returned unchanged.
+    fn recall_history(&mut self, line: &str) -> FileResult<String> {
+        let Some(reference) = line.strip_prefix('!') else {
+            return Ok(line.to_string());
+        };
+
+        let recalled = if reference == "!" {
+            self.history.last()
+        } else {
+            let number = reference.parse::<usize>().map_err(|_| {
+                FileError::InvalidInput(format!("Invalid history reference '{}': use !! or !<number>", line))
+            })?;
+            number.checked_sub(1).and_then(|index| self.history.get(index))
+        };
+
+        let recalled = recalled
+            .cloned()
+            .ok_or_else(|| FileError::InvalidInput(format!("No command {} in history", line)))?;
+        writeln!(self.output, "{}", recalled)?;
+        Ok(recalled)
+    }
+
+    /// Runs each line of a script file through `execute_command`, skipping blank lines and
+    /// `#` comments and stopping early at `quit`. Failed lines don't stop the script; they
+    /// are reported in a summary and returned together as a `BatchError`.
+    pub fn run_batch(&mut self, path: &Path) -> FileResult<()> {
+        let script = std::fs::read_to_string(path)?;
+        let mut succeeded = 0;
+        let mut errors: Vec<(usize, FileError)> = Vec::new();
+
+        for (number, line) in (1..).zip(script.lines()) {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match self.execute_command(line) {
+                Ok(true) => succeeded += 1,
+                Ok(false) => {
+                    succeeded += 1;
+                    break;
+                }
+                Err(e) => errors.push((number, e)),
+            }
+        }
+
+        writeln!(self.output, "📜 Script finished: {} succeeded, {} failed", succeeded, errors.len())?;
+        for (number, e) in &errors {
+            writeln!(self.output, "  {}", self.color.red(&format!("❌ Line {}: {}", number, e)))?;
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(FileError::BatchError(
+                errors.into_iter().map(|(number, e)| e.with_context(format!("line {}", number))).collect(),
+            ))
+        }
+    }
+
+    /// Runs one command line as if it had been typed at the prompt, writing output to the
+    /// configured writer. Inline arguments answer the command's prompts in order; any
+    /// that are missing are prompted for. Unknown flags and extra arguments are errors.
+    /// Returns `false` for `quit` and `true` otherwise.
+    pub fn execute_command(&mut self, line: &str) -> FileResult<bool> {
+        let (operation, args) = Operation::parse(line)?;
+        self.run_operation(operation, args)
+    }
+
+    /// Runs a single command given as program arguments, e.g. `["read", "notes.txt"]`,
+    /// optionally preceded by `--file <path>` to load a snapshot first and `--force`
+    /// to skip confirmations. Errors are written
+    /// to the output and also returned, including those the command only reported.
+    pub fn run_args(&mut self, args: &[String]) -> FileResult<()> {
+        if let Err(e) = self.execute_args(args) {
+            self.report_error(&e)?;
+        }
+
+        match self.last_error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn execute_args(&mut self, args: &[String]) -> FileResult<()> {
+        let mut args = args;
+        loop {
+            match args {
+                [flag, path, rest @ ..] if flag == "--file" => {
+                    self.filesystem = FileSystem::restore(&std::fs::read(path)?)?;
+                    args = rest;
                 }
-                Err(e) => println!("❌ {}", e),
+                [flag, rest @ ..] if flag == "--force" => {
+                    self.confirm = false;
+                    args = rest;
+                }
+                _ => break,
             }
         }
 
-        println!("👋 Goodbye!");
+        let [command, rest @ ..] = args else {
+            return Err(FileError::InvalidInput(
+                "Usage: cli [--file <snapshot>] [--force] <command> [args...]".to_string(),
+            ));
+        };
+        let (operation, prompt_args) = Operation::parse_args(command, rest.to_vec())?;
+        self.run_operation(operation, prompt_args)?;
+        Ok(())
+    }
+
+    /// Executes an operation with its inline arguments, then refreshes the index file
+    /// and reports changes to watched files
+    fn run_operation(&mut self, operation: Operation, args: Vec<String>) -> FileResult<bool> {
+        self.last_error = None;
+        self.inline = !args.is_empty();
+        (self.pending_args, self.pending_flags) = operation.arg_spec().parse(args)?;
+        let result = self.execute_operation(operation);
+        self.pending_args.clear();
+        self.pending_flags.clear();
+
+        if let Err(e) = self.filesystem.refresh_index() {
+            self.report_error(&e)?;
+        }
+        self.report_watched_changes()?;
+        result
+    }
+
+    /// Prints an error that a command handles itself, remembering it for `run_args`
+    fn report_error(&mut self, error: &FileError) -> FileResult<()> {
+        writeln!(self.output, "{}", self.color.red(&format!("❌ {}", error)))?;
+        self.last_error = Some(error.clone());
         Ok(())
     }
 
@@ -92,22 +869,78 @@ impl CLI {
                 self.write_file()?;
                 Ok(true)
             }
+            Operation::Append => {
+                self.append_file()?;
+                Ok(true)
+            }
             Operation::Read => {
                 self.read_file()?;
                 Ok(true)
             }
-            Operation::List => {
-                self.list_files()?;
+            Operation::ReadLines => {
+                self.read_lines()?;
+                Ok(true)
+            }
+            Operation::List(filter) => {
+                self.list_files(&filter)?;
                 Ok(true)
             }
             Operation::Delete => {
                 self.delete_file()?;
                 Ok(true)
             }
+            Operation::Truncate => {
+                self.truncate_file()?;
+                Ok(true)
+            }
+            Operation::Replace => {
+                self.replace_in_file()?;
+                Ok(true)
+            }
+            Operation::ReplaceAll => {
+                self.replace_all()?;
+                Ok(true)
+            }
+            Operation::Copy => {
+                self.copy_file()?;
+                Ok(true)
+            }
+            Operation::Concat => {
+                self.concat_files()?;
+                Ok(true)
+            }
+            Operation::Split => {
+                self.split_file()?;
+                Ok(true)
+            }
+            Operation::Move => {
+                self.rename_file()?;
+                Ok(true)
+            }
             Operation::Info => {
                 self.show_file_info()?;
                 Ok(true)
             }
+            Operation::Search => {
+                self.search_files()?;
+                Ok(true)
+            }
+            Operation::Glob => {
+                self.glob_files()?;
+                Ok(true)
+            }
+            Operation::Recent => {
+                self.recent_files()?;
+                Ok(true)
+            }
+            Operation::Sort(by, order) => {
+                self.sort_files(by, order)?;
+                Ok(true)
+            }
+            Operation::History => {
+                self.show_history()?;
+                Ok(true)
+            }
             Operation::Help => {
                 self.show_help()?;
                 Ok(true)
@@ -116,178 +949,1683 @@ impl CLI {
                 self.show_stats()?;
                 Ok(true)
             }
-            Operation::Quit => Ok(false),
-        }
-    }
-
-    /// Creates a new file
-    fn create_file(&mut self) -> FileResult<()> {
-        println!("Creating file...");
-        
-        let name = self.get_input("Enter file name: ")?;
-        let content = self.get_input("Enter file content: ")?;
-
-        match self.filesystem.create_file(name.clone(), content) {
-            Ok(id) => println!("✅ File '{}' created successfully with ID: {}", name, id),
-            Err(e) => println!("❌ {}", e),
-        }
-        Ok(())
-    }
-
-    /// Writes content to an existing file
-    fn write_file(&mut self) -> FileResult<()> {
-        println!("Writing content...");
-        
-        let name = self.get_input("Enter file name: ")?;
-        let content = self.get_input("Enter new content: ")?;
-
-        match self.filesystem.write_file(&name, content) {
-            Ok(()) => println!("✅ Content written to '{}' successfully", name),
-            Err(e) => println!("❌ {}", e),
-        }
-        Ok(())
-    }
-
-    /// Reads a file's content
-    fn read_file(&mut self) -> FileResult<()> {
-        println!("Reading file...");
-        
-        let name = self.get_input("Enter file name: ")?;
-
-        match self.filesystem.read_file(&name) {
-            Ok(content) => {
-                println!("📄 Content of '{}':", name);
-                println!("{}", "-".repeat(40));
-                println!("{}", content);
-                println!("{}", "-".repeat(40));
+            Operation::StatsJson => {
+                self.show_stats_json()?;
+                Ok(true)
             }
-            Err(e) => println!("❌ {}", e),
-        }
-        Ok(())
-    }
-
-    /// Lists all files
-    fn list_files(&mut self) -> FileResult<()> {
-        println!("Listing files...");
-        
-        let files = self.filesystem.list_files();
-        
-        if files.is_empty() {
-            println!("📭 No files found.");
-        } else {
-            println!("📂 Files in system:");
-            for file in files {
-                println!("  {}", file.display_summary());
+            Operation::CsvSort => {
+                self.csv_sort()?;
+                Ok(true)
             }
-        }
-        Ok(())
-    }
-
-    /// Deletes a file
-    fn delete_file(&mut self) -> FileResult<()> {
-        println!("Deleting file...");
-        
-        let input = self.get_input("Enter file name or ID: ")?;
-        
-        // Try to parse as ID first, then as name
-        let result = if let Ok(id) = input.parse::<u32>() {
-            self.filesystem.delete_file_by_id(id)
-        } else {
-            self.filesystem.delete_file(&input)
-        };
-
-        match result {
-            Ok(()) => println!("✅ File deleted successfully"),
-            Err(e) => println!("❌ {}", e),
+            Operation::Lint => {
+                self.lint_names()?;
+                Ok(true)
+            }
+            Operation::CommonPrefix => {
+                self.common_affix(false)?;
+                Ok(true)
+            }
+            Operation::CommonSuffix => {
+                self.common_affix(true)?;
+                Ok(true)
+            }
+            Operation::BatchRename => {
+                self.batch_rename()?;
+                Ok(true)
+            }
+            Operation::ZipRead => {
+                self.zip_read()?;
+                Ok(true)
+            }
+            Operation::SearchContext => {
+                self.search_context()?;
+                Ok(true)
+            }
+            Operation::Readability => {
+                self.show_readability()?;
+                Ok(true)
+            }
+            Operation::Pad => {
+                self.pad_file()?;
+                Ok(true)
+            }
+            Operation::Symlink => {
+                self.create_symlink()?;
+                Ok(true)
+            }
+            Operation::Hardlink => {
+                self.create_hardlink()?;
+                Ok(true)
+            }
+            Operation::Pipeline => {
+                self.run_pipeline()?;
+                Ok(true)
+            }
+            Operation::ExportRss => {
+                self.export_rss()?;
+                Ok(true)
+            }
+            Operation::ImportRss => {
+                self.import_rss()?;
+                Ok(true)
+            }
+            Operation::ExportJson => {
+                self.export_json()?;
+                Ok(true)
+            }
+            Operation::ImportJson => {
+                self.import_json()?;
+                Ok(true)
+            }
+            Operation::ExportDir => {
+                self.export_dir()?;
+                Ok(true)
+            }
+            Operation::ImportDir => {
+                self.import_dir()?;
+                Ok(true)
+            }
+            Operation::IdReport => {
+                self.show_id_report()?;
+                Ok(true)
+            }
+            Operation::AutoTag => {
+                self.auto_tag()?;
+                Ok(true)
+            }
+            Operation::AutoTagAll => {
+                self.auto_tag_all()?;
+                Ok(true)
+            }
+            Operation::Xref => {
+                self.cross_reference()?;
+                Ok(true)
+            }
+            Operation::Average => {
+                self.average_content()?;
+                Ok(true)
+            }
+            Operation::GrepCollect => {
+                self.grep_collect()?;
+                Ok(true)
+            }
+            Operation::Grep => {
+                self.grep_file()?;
+                Ok(true)
+            }
+            Operation::Watch => {
+                self.watch_file()?;
+                Ok(true)
+            }
+            Operation::MakeIndex => {
+                self.make_index()?;
+                Ok(true)
+            }
+            Operation::Tag => {
+                self.tag_file(true)?;
+                Ok(true)
+            }
+            Operation::Untag => {
+                self.tag_file(false)?;
+                Ok(true)
+            }
+            Operation::Lock => {
+                self.lock_file(true)?;
+                Ok(true)
+            }
+            Operation::Unlock => {
+                self.lock_file(false)?;
+                Ok(true)
+            }
+            Operation::ReadOnly => {
+                self.set_readonly(true)?;
+                Ok(true)
+            }
+            Operation::ReadWrite => {
+                self.set_readonly(false)?;
+                Ok(true)
+            }
+            Operation::Chmod => {
+                self.change_permissions()?;
+                Ok(true)
+            }
+            Operation::Dupes => {
+                self.show_duplicates()?;
+                Ok(true)
+            }
+            Operation::Diff => {
+                self.diff_files()?;
+                Ok(true)
+            }
+            Operation::Versions => {
+                self.list_versions()?;
+                Ok(true)
+            }
+            Operation::Revert => {
+                self.revert_file()?;
+                Ok(true)
+            }
+            Operation::Mkdir => {
+                self.make_directory()?;
+                Ok(true)
+            }
+            Operation::Rmdir => {
+                self.remove_directory()?;
+                Ok(true)
+            }
+            Operation::Cd => {
+                self.change_directory()?;
+                Ok(true)
+            }
+            Operation::CaseInsensitive(enabled) => {
+                self.set_case_insensitive(enabled)?;
+                Ok(true)
+            }
+            Operation::Confirm(enabled) => {
+                self.set_confirm(enabled)?;
+                Ok(true)
+            }
+            Operation::PageSize(page_size) => {
+                self.set_page_size(page_size)?;
+                Ok(true)
+            }
+            Operation::Top(count, largest) => {
+                self.show_top(count, largest)?;
+                Ok(true)
+            }
+            Operation::Pwd => {
+                writeln!(self.output, "📁 {}", self.filesystem.pwd())?;
+                Ok(true)
+            }
+            Operation::Quit => Ok(false),
+        }
+    }
+
+    /// Creates a new file
+    fn create_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Creating file...")?;
+        
+        let name = self.get_input("Enter file name: ")?;
+        let content = self.get_multiline_input("Enter file content: ")?;
+
+        match self.filesystem.create_file(name.clone(), content) {
+            Ok(id) => writeln!(self.output, "{}", self.color.green(&format!("✅ File '{}' created successfully with ID: {}", name, id)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Writes content to an existing file
+    fn write_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Writing content...")?;
+        
+        let name = self.get_file_path("Enter file name: ")?;
+        let content = self.get_multiline_input("Enter new content: ")?;
+
+        match self.filesystem.write_file(&name, content) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ Content written to '{}' successfully", name)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Appends content to an existing file
+    fn append_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Appending content...")?;
+
+        let name = self.get_file_path("Enter file name: ")?;
+        let content = self.get_optional_multiline_input("Enter content to append: ")?;
+
+        match self.filesystem.append_file(&name, &content) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ Content appended to '{}' successfully", name)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Reads a file's content
+    fn read_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Reading file...")?;
+        
+        let input = self.get_file_name("Enter file name: ")?;
+        let name = self.resolve_name(&input)?;
+
+        match self.filesystem.read_file(&name) {
+            Ok(content) => {
+                writeln!(self.output, "{}", self.color.bold(&format!("📄 Content of '{}':", name)))?;
+                writeln!(self.output, "{}", "-".repeat(40))?;
+                writeln!(self.output, "{}", content)?;
+                writeln!(self.output, "{}", "-".repeat(40))?;
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Reads a range of lines from a file
+    fn read_lines(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let parse_line = |input: String| {
+            input.parse::<usize>()
+                .map_err(|_| FileError::InvalidInput("Line numbers must be positive numbers".to_string()))
+        };
+        let start = parse_line(self.get_input("Enter start line: ")?)?;
+        let end = parse_line(self.get_input("Enter end line: ")?)?;
+
+        match self.filesystem.read_lines(&name, start, end) {
+            Ok(lines) => {
+                writeln!(self.output, "{}", self.color.bold(&format!("📄 Lines {}-{} of '{}':", start, end, name)))?;
+                writeln!(self.output, "{}", "-".repeat(40))?;
+                for (number, line) in (start..).zip(lines) {
+                    writeln!(self.output, "{:>4}: {}", number, line)?;
+                }
+                writeln!(self.output, "{}", "-".repeat(40))?;
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Lists all files
+    fn list_files(&mut self, filter: &ListFilter) -> FileResult<()> {
+        writeln!(self.output, "Listing files...")?;
+
+        let files = match filter {
+            ListFilter::All => return self.list_all_entries(),
+            ListFilter::Extension(ext) => self.filesystem.filter_by_extension(ext),
+            ListFilter::NoExtension => self.filesystem.filter_by_no_extension(),
+            ListFilter::Size { min, max } => self.filesystem.filter_by_size_range(*min, *max)?,
+        };
+
+        if files.is_empty() {
+            writeln!(self.output, "📭 No matching files found.")?;
+        } else {
+            let lines: Vec<String> = files
+                .iter()
+                .map(|file| format!("  {}", self.color.yellow(&file.display_summary())))
+                .collect();
+            writeln!(self.output, "{}", self.color.bold("📂 Matching files:"))?;
+            paginate(&lines, self.page_size, &mut self.input, &mut self.output)?;
+        }
+        Ok(())
+    }
+
+    /// Lists the subdirectories and files in the current directory
+    fn list_all_entries(&mut self) -> FileResult<()> {
+        let (subdirs, entries) = self.filesystem.list_cwd();
+
+        if subdirs.is_empty() && entries.is_empty() {
+            writeln!(self.output, "📭 No files found.")?;
+        } else {
+            let lines: Vec<String> = subdirs
+                .iter()
+                .map(|subdir| format!("  [dir] {}", self.color.yellow(&format!("{}/", subdir))))
+                .chain(entries.iter().map(|entry| format!("  {} {}", entry.kind(), self.color.yellow(&entry.display_summary()))))
+                .collect();
+            writeln!(self.output, "{}", self.color.bold(&format!("📂 Files in {}:", self.filesystem.pwd())))?;
+            paginate(&lines, self.page_size, &mut self.input, &mut self.output)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes a file
+    fn delete_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Deleting file...")?;
+        
+        let input = self.get_file_name("Enter file name or ID: ")?;
+
+        // Find the file first, trying the input as an ID and then as a name, so a
+        // missing file is reported without asking and the question names the file
+        let target = if let Ok(id) = input.parse::<u32>() {
+            self.filesystem.path_of(id)
+        } else {
+            self.resolve_name(&input)
+        };
+        let target = match target {
+            Ok(target) => target,
+            Err(e) => {
+                self.report_error(&e)?;
+                return Ok(());
+            }
+        };
+
+        if !self.ask_confirm(&format!("Delete '{}'?", target))? {
+            writeln!(self.output, "↩️  Delete cancelled")?;
+            return Ok(());
+        }
+
+        match self.filesystem.delete_file(&target) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green("✅ File deleted successfully"))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Empties a file's content
+    fn truncate_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Truncating file...")?;
+
+        let name = self.get_file_path("Enter file name: ")?;
+        if !self.ask_confirm(&format!("Truncate '{}'?", name))? {
+            writeln!(self.output, "↩️  Truncate cancelled")?;
+            return Ok(());
+        }
+
+        match self.filesystem.truncate_file(&name) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' truncated", name)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Replaces text within a single file
+    fn replace_in_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Replacing text...")?;
+
+        let name = self.get_file_path("Enter file name: ")?;
+        let from = self.get_input("Enter text to find: ")?;
+        let to = self.read_line("Enter replacement text: ")?;
+        let all = self.get_switch("--all", "Replace all occurrences? (y/N): ")?;
+
+        match self.filesystem.replace_in_file(&name, &from, &to, all) {
+            Ok(0) => writeln!(self.output, "📭 '{}' not found in '{}'", from, name)?,
+            Ok(count) => writeln!(self.output, "{}", self.color.green(&format!("✅ Made {} replacement(s) in '{}'", count, name)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Replaces text across every file
+    fn replace_all(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Replacing text in all files...")?;
+
+        let from = self.get_input("Enter text to find: ")?;
+        let to = self.read_line("Enter replacement text: ")?;
+
+        match self.filesystem.replace_all(&from, &to) {
+            Ok(counts) if counts.is_empty() => writeln!(self.output, "📭 '{}' not found in any file", from)?,
+            Ok(counts) => {
+                let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+                counts.sort();
+                writeln!(self.output, "{}", self.color.green(&format!("✅ Changed {} file(s):", counts.len())))?;
+                for (path, count) in counts {
+                    writeln!(self.output, "  {}: {} replacement(s)", path, count)?;
+                }
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Copies a file to a new name
+    fn copy_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Copying file...")?;
+
+        let source = self.get_file_path("Enter source file name: ")?;
+        let dest = self.get_input("Enter destination file name: ")?;
+
+        match self.filesystem.copy_file(&source, &dest) {
+            Ok(id) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' copied to '{}' with ID: {}", source, dest, id)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Combines two files into a new one
+    fn concat_files(&mut self) -> FileResult<()> {
+        let first = self.get_file_path("Enter first file name: ")?;
+        let second = self.get_file_path("Enter second file name: ")?;
+        let dest = self.get_input("Enter destination file name: ")?;
+        let separator = self.get_optional("Enter separator (leave empty for a newline): ")?;
+        let separator = if separator.is_empty() { "\n" } else { separator.as_str() };
+
+        match self.filesystem.concat_files_with(&first, &second, dest.clone(), separator) {
+            Ok(id) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' and '{}' combined into '{}' with ID: {}", first, second, dest, id)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Moves the end of a file into a new file
+    fn split_file(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let at_line = self.get_input("Enter the last line to keep: ")?
+            .parse::<usize>()
+            .map_err(|_| FileError::InvalidInput("Line number must be a positive number".to_string()))?;
+        let second_name = self.get_input("Enter new file name for the rest: ")?;
+
+        match self.filesystem.split_file(&name, at_line, second_name.clone()) {
+            Ok(id) => writeln!(self.output, "{}", self.color.green(&format!("✅ Lines after {} of '{}' moved to '{}' with ID: {}", at_line, name, second_name, id)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Renames a file
+    fn rename_file(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Renaming file...")?;
+
+        let old_name = self.get_file_path("Enter current file name: ")?;
+        let new_name = self.get_input("Enter new file name: ")?;
+
+        match self.filesystem.rename_file(&old_name, &new_name) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' renamed to '{}'", old_name, new_name)))?,
+            Err(e) => self.report_error(&e)?,
         }
         Ok(())
     }
 
     /// Shows detailed file information
     fn show_file_info(&mut self) -> FileResult<()> {
-        println!("File information...");
+        writeln!(self.output, "File information...")?;
         
-        let input = self.get_input("Enter file name or ID: ")?;
+        let input = self.get_file_name("Enter file name or ID: ")?;
         
         // Try to parse as ID first, then as name
         let file = if let Ok(id) = input.parse::<u32>() {
             self.filesystem.get_file_by_id(id)
         } else {
-            self.filesystem.get_file(&input)
+            self.resolve_name(&input).and_then(|name| self.filesystem.get_file(&name))
         };
 
         match file {
             Ok(file) => {
-                println!("📋 File Information:");
-                println!("{}", file.display_detailed());
+                writeln!(self.output, "{}", self.color.bold("📋 File Information:"))?;
+                writeln!(self.output, "{}", file.display_detailed())?;
             }
-            Err(e) => println!("❌ {}", e),
+            Err(e) => self.report_error(&e)?,
         }
         Ok(())
     }
 
-    /// Shows help information
-    fn show_help(&mut self) -> FileResult<()> {
-        println!("📚 Available Commands:");
-        println!("  create, c    - Create a new file");
-        println!("  write, w     - Write content to an existing file");
-        println!("  read, r      - Read file content");
-        println!("  list, l, ls  - List all files");
-        println!("  delete, d    - Delete a file (by name or ID)");
-        println!("  info, i      - Show detailed file information");
-        println!("  stats, s     - Show system statistics");
-        println!("  help, h, ?   - Show this help message");
-        println!("  quit, q      - Exit the program");
+    /// Finds files whose content contains a query
+    fn search_files(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Searching files...")?;
+
+        let query = self.read_line("Enter search text: ")?;
+        let ignore_case = self.get_switch("--ignore-case", "Ignore case? (y/N): ")?;
+
+        let files = if ignore_case {
+            self.filesystem.find_by_content_ignore_case(&query)
+        } else {
+            self.filesystem.find_by_content(&query)
+        };
+
+        if files.is_empty() {
+            writeln!(self.output, "📭 No files contain '{}'", query)?;
+        } else {
+            writeln!(self.output, "{}", self.color.bold("🔍 Matching files:"))?;
+            for file in files {
+                writeln!(self.output, "  {}", self.color.yellow(&file.display_summary()))?;
+            }
+        }
         Ok(())
     }
 
-    /// Shows system statistics
-    fn show_stats(&mut self) -> FileResult<()> {
-        println!("📊 System Statistics:");
-        println!("  Total files: {}", self.filesystem.file_count());
-        println!("  Total size: {} bytes", self.filesystem.total_size());
-        
-        let files = self.filesystem.list_files();
-        if !files.is_empty() {
-            let avg_size = self.filesystem.total_size() / files.len();
-            println!("  Average file size: {} bytes", avg_size);
-            
-            // Show file type distribution
-            let mut extensions: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    /// Lists files whose names match a glob pattern
+    fn glob_files(&mut self) -> FileResult<()> {
+        let pattern = self.get_input("Enter pattern (e.g. *.txt): ")?;
+
+        let files = self.filesystem.find_by_name_pattern(&pattern);
+        if files.is_empty() {
+            writeln!(self.output, "📭 No files match '{}'", pattern)?;
+        } else {
+            writeln!(self.output, "{}", self.color.bold("🔍 Matching files:"))?;
             for file in files {
-                let ext = file.extension().unwrap_or("no extension").to_string();
-                *extensions.entry(ext).or_insert(0) += 1;
-            }
-            
-            if !extensions.is_empty() {
-                println!("  File types:");
-                for (ext, count) in extensions {
-                    println!("    .{}: {} files", ext, count);
-                }
+                writeln!(self.output, "  {}", self.color.yellow(&file.display_summary()))?;
             }
         }
         Ok(())
     }
 
-    /// Gets user input with a prompt
-    fn get_input(&self, prompt: &str) -> FileResult<String> {
-        print!("{}", prompt);
-        io::stdout().flush().unwrap();
+    /// Lists files created within the last N seconds
+    fn recent_files(&mut self) -> FileResult<()> {
+        let seconds = self.get_input("Enter number of seconds: ")?
+            .parse::<u64>()
+            .map_err(|_| FileError::InvalidInput("Seconds must be a non-negative number".to_string()))?;
+        let since = std::time::SystemTime::now()
+            .checked_sub(std::time::Duration::from_secs(seconds))
+            .unwrap_or(std::time::UNIX_EPOCH);
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)
-            .map_err(|e| FileError::InvalidInput(format!("Failed to read input: {}", e)))?;
+        let files = self.filesystem.files_created_after(since);
+        if files.is_empty() {
+            writeln!(self.output, "📭 No files created in the last {} seconds", seconds)?;
+        } else {
+            writeln!(self.output, "{}", self.color.bold(&format!("🕒 Files created in the last {} seconds:", seconds)))?;
+            for file in files {
+                writeln!(self.output, "  {}", self.color.yellow(&file.display_summary()))?;
+            }
+        }
+        Ok(())
+    }
 
-        let trimmed = input.trim().to_string();
-        if trimmed.is_empty() {
-            return Err(FileError::InvalidInput("Input cannot be empty".to_string()));
+    /// Lists text files in the requested order
+    fn sort_files(&mut self, by: SortField, order: SortOrder) -> FileResult<()> {
+        let files = self.filesystem.sorted_files(by, order);
+        if files.is_empty() {
+            writeln!(self.output, "📭 No files found.")?;
+        } else {
+            writeln!(self.output, "{}", self.color.bold(&format!("📂 Files sorted by {:?} ({:?}):", by, order)))?;
+            for file in files {
+                writeln!(self.output, "  {}", self.color.yellow(&file.display_summary()))?;
+            }
         }
+        Ok(())
+    }
 
-        Ok(trimmed)
+    /// Lists the largest or smallest files
+    fn show_top(&mut self, count: usize, largest: bool) -> FileResult<()> {
+        let (files, label) = if largest {
+            (self.filesystem.top_n_largest(count), "largest")
+        } else {
+            (self.filesystem.top_n_smallest(count), "smallest")
+        };
+
+        if files.is_empty() {
+            writeln!(self.output, "📭 No files found.")?;
+        } else {
+            writeln!(self.output, "{}", self.color.bold(&format!("📊 Top {} {} files:", files.len(), label)))?;
+            for (rank, file) in files.iter().enumerate() {
+                writeln!(self.output, "  {}. {}", rank + 1, self.color.yellow(&file.display_summary()))?;
+            }
+        }
+        Ok(())
     }
+
+    /// Lists the commands entered so far, numbered for `!n`
+    fn show_history(&mut self) -> FileResult<()> {
+        if self.history.is_empty() {
+            writeln!(self.output, "📭 No commands in history.")?;
+        } else {
+            for (number, line) in (1..).zip(&self.history) {
+                writeln!(self.output, "{:>4}  {}", number, line)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows help information
+    fn show_help(&mut self) -> FileResult<()> {
+        writeln!(self.output, "{}", self.color.bold("📚 Available Commands:"))?;
+        writeln!(self.output, "  create, c    - Create a new file")?;
+        writeln!(self.output, "  write, w     - Write content to an existing file")?;
+        writeln!(self.output, "  append, a    - Append content to an existing file")?;
+        writeln!(self.output, "  read, r      - Read file content")?;
+        writeln!(self.output, "  readlines    - Read a range of lines from a file")?;
+        writeln!(self.output, "  list, l, ls  - List all files (filter with --ext <ext>, --no-ext, --min/--max <bytes>)")?;
+        writeln!(self.output, "  delete, d    - Delete a file (by name or ID); --force skips the confirmation")?;
+        writeln!(self.output, "  truncate     - Empty a file but keep its ID; --force skips the confirmation")?;
+        writeln!(self.output, "  replace      - Replace text within a file: replace <file> <text> <replacement> [--all]")?;
+        writeln!(self.output, "  replace-all  - Replace text in every file, listing changes by path")?;
+        writeln!(self.output, "  copy, cp     - Copy a file to a new name")?;
+        writeln!(self.output, "  concat       - Combine two files into a new file")?;
+        writeln!(self.output, "  split        - Move the lines after a given line into a new file")?;
+        writeln!(self.output, "  move, mv     - Rename a file, keeping its ID")?;
+        writeln!(self.output, "  info, i      - Show detailed file information")?;
+        writeln!(self.output, "  search, find - Find files containing some text (--ignore-case, -i)")?;
+        writeln!(self.output, "  glob         - List files matching a name pattern (* and ?)")?;
+        writeln!(self.output, "  recent       - List files created in the last N seconds")?;
+        writeln!(self.output, "  sort         - List files sorted: sort [name|size|id|created] [asc|desc]")?;
+        writeln!(self.output, "  top          - List the biggest or smallest files: top <count> [largest|smallest]")?;
+        writeln!(self.output, "  stats, s     - Show text file statistics (stats --json for JSON)")?;
+        writeln!(self.output, "  csv-sort     - Sort a CSV file by a column: csv-sort <file> --col <n> [--numeric] [--desc]")?;
+        writeln!(self.output, "  lint         - Check file names for problems; lint --fix renames them")?;
+        writeln!(self.output, "  common-prefix - Show the common prefix of file names: common-prefix <name>...")?;
+        writeln!(self.output, "  common-suffix - Show the common suffix of file names: common-suffix <name>...")?;
+        writeln!(self.output, "  batch-rename - Rename files matching a regex")?;
+        writeln!(self.output, "  zip-read     - Show paired files side by side")?;
+        writeln!(self.output, "  search-context - Search a file showing surrounding lines: search-context <file> <pattern> [-C <lines>]")?;
+        writeln!(self.output, "  readability  - Show the Flesch reading ease of a file")?;
+        writeln!(self.output, "  pad          - Pad a file to a target size: pad <file> <bytes> [--char <c>]")?;
+        writeln!(self.output, "  symlink, ln  - Create a symlink to another file")?;
+        writeln!(self.output, "  hardlink     - Create a new name sharing a file's content")?;
+        writeln!(self.output, "  pipeline     - Transform a file (trim, lower, upper, lf, crlf, strip-blank, wrap:N, rot13)")?;
+        writeln!(self.output, "  export-rss   - Print all files as an RSS feed: export-rss --title <title> --link <url> [--description <text>]")?;
+        writeln!(self.output, "  import-rss   - Create files from an RSS feed stored in a file")?;
+        writeln!(self.output, "  export       - Print all text files and directories as JSON")?;
+        writeln!(self.output, "  import       - Create files from JSON stored in a file")?;
+        writeln!(self.output, "  export-dir   - Write all files to a directory on disk")?;
+        writeln!(self.output, "  import-dir   - Create files from a directory on disk")?;
+        writeln!(self.output, "  id-report    - Show ID allocation and gaps")?;
+        writeln!(self.output, "  auto-tag     - Tag a file based on its name and content")?;
+        writeln!(self.output, "  auto-tag-all - Auto-tag every file")?;
+        writeln!(self.output, "  xref         - List files that mention a file name")?;
+        writeln!(self.output, "  average      - Majority-vote \"average\" of several files: average <file>...")?;
+        writeln!(self.output, "  grep-collect - Save all matching lines into a new file (--case-sensitive)")?;
+        writeln!(self.output, "  grep         - Show matching lines with line numbers")?;
+        writeln!(self.output, "  watch        - Print a diff whenever a file changes")?;
+        writeln!(self.output, "  make-index   - Create a self-updating table of contents file")?;
+        writeln!(self.output, "  tag          - Add a tag to a file")?;
+        writeln!(self.output, "  untag        - Remove a tag from a file")?;
+        writeln!(self.output, "  lock         - Make a file read-only")?;
+        writeln!(self.output, "  unlock       - Make a locked file writable again")?;
+        writeln!(self.output, "  readonly     - Reject all changes to the filesystem")?;
+        writeln!(self.output, "  readwrite    - Allow changes to the filesystem again")?;
+        writeln!(self.output, "  chmod        - Set a file's permissions (e.g. rw-, r--)")?;
+        writeln!(self.output, "  dupes        - Find files with identical content")?;
+        writeln!(self.output, "  diff         - Show line differences between two files")?;
+        writeln!(self.output, "  versions     - List a file's previous versions")?;
+        writeln!(self.output, "  revert       - Restore a previous version of a file")?;
+        writeln!(self.output, "  mkdir        - Create a directory")?;
+        writeln!(self.output, "  rmdir        - Remove a directory; --recursive (-r) removes its contents too")?;
+        writeln!(self.output, "  cd           - Change the current directory")?;
+        writeln!(self.output, "  pwd          - Show the current directory")?;
+        writeln!(self.output, "  set          - Settings: set [case-insensitive|case-sensitive|confirm on|confirm off|pagesize <lines>]")?;
+        writeln!(self.output, "  history      - List previous commands; !n reruns command n and !! the last one")?;
+        writeln!(self.output, "  help, h, ?   - Show this help message")?;
+        writeln!(self.output, "  quit, q      - Exit the program")?;
+        writeln!(self.output, "Prompts can be answered inline, e.g. create notes.txt \"line one\\nline two\"")?;
+        writeln!(self.output, "Flags answer yes/no questions and can go anywhere; -- ends the flags")?;
+        Ok(())
+    }
+
+    /// Shows system statistics
+    fn show_stats(&mut self) -> FileResult<()> {
+        writeln!(self.output, "{}", self.color.bold("📊 System Statistics:"))?;
+        writeln!(self.output, "{}", self.filesystem.stats_report().to_text())?;
+        Ok(())
+    }
+
+    /// Prints system statistics as JSON
+    fn show_stats_json(&mut self) -> FileResult<()> {
+        writeln!(self.output, "{}", self.filesystem.stats_report().to_json())?;
+        Ok(())
+    }
+
+    /// Sorts a CSV file by a column
+    fn csv_sort(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Sorting CSV file...")?;
+
+        let name = self.get_file_path("Enter file name: ")?;
+        let column = self.get_option("--col", "Enter column index (0-based): ")?
+            .parse::<usize>()
+            .map_err(|_| FileError::InvalidInput("Column must be a non-negative number".to_string()))?;
+        let numeric = self.get_switch("--numeric", "Sort numerically? (y/N): ")?;
+        let descending = self.get_switch("--desc", "Sort descending? (y/N): ")?;
+
+        match self.filesystem.csv_sort(&name, column, numeric, descending) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' sorted by column {}", name, column)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Reports problematic file names, and fixes them when given `--fix`
+    fn lint_names(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Linting file names...")?;
+        let fix = self.pending_flags.remove("--fix").is_some();
+
+        let results = self.filesystem.lint_names();
+        if results.is_empty() {
+            writeln!(self.output, "{}", self.color.green("✅ All file names look good"))?;
+            return Ok(());
+        }
+
+        for (file, warnings) in &results {
+            writeln!(self.output, "⚠️  [{}] {}", file.id, file.name)?;
+            for warning in warnings {
+                writeln!(self.output, "    - {}", warning)?;
+            }
+        }
+
+        if !fix {
+            writeln!(self.output, "Run 'lint --fix' to rename them automatically")?;
+            return Ok(());
+        }
+
+        match self.filesystem.fix_lint_warnings() {
+            Ok(renamed) => {
+                for (old_name, new_name) in renamed {
+                    writeln!(self.output, "{}", self.color.green(&format!("✅ Renamed '{}' to '{}'", old_name, new_name)))?;
+                }
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Shows the common prefix or suffix of several file names
+    fn common_affix(&mut self, suffix: bool) -> FileResult<()> {
+        let names = self.get_list("Enter file names (space-separated): ")?;
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        let result = if suffix {
+            self.filesystem.find_longest_common_suffix(&names)
+        } else {
+            self.filesystem.find_longest_common_prefix(&names)
+        };
+
+        match result {
+            Ok(affix) if affix.is_empty() => writeln!(self.output, "📭 No common {}", if suffix { "suffix" } else { "prefix" })?,
+            Ok(affix) => writeln!(self.output, "🔗 Common {}: '{}'", if suffix { "suffix" } else { "prefix" }, affix)?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Renames all files matching a regex pattern
+    fn batch_rename(&mut self) -> FileResult<()> {
+        writeln!(self.output, "Batch renaming files...")?;
+
+        let from = self.get_input("Enter name pattern (regex): ")?;
+        let to = self.get_input("Enter replacement (use $1 for groups): ")?;
+
+        match self.filesystem.batch_rename(&from, &to) {
+            Ok(pairs) if pairs.is_empty() => writeln!(self.output, "📭 No files matched")?,
+            Ok(pairs) => {
+                for (old_name, new_name) in &pairs {
+                    writeln!(self.output, "  {} -> {}", old_name, new_name)?;
+                }
+                writeln!(self.output, "{}", self.color.green(&format!("✅ Renamed {} files", pairs.len())))?;
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Prints pairs of files side by side
+    fn zip_read(&mut self) -> FileResult<()> {
+        let left = self.get_input("Enter first file list (comma-separated): ")?;
+        let right = self.get_input("Enter second file list (comma-separated): ")?;
+        let left: Vec<&str> = left.split(',').map(str::trim).collect();
+        let right: Vec<&str> = right.split(',').map(str::trim).collect();
+
+        match self.filesystem.zip_files(&left, &right) {
+            Ok(pairs) => {
+                for (a, b) in pairs {
+                    let a_lines: Vec<&str> = a.content.lines().collect();
+                    let b_lines: Vec<&str> = b.content.lines().collect();
+                    let width = a_lines.iter().map(|l| l.chars().count()).max().unwrap_or(0).max(a.name.chars().count());
+
+                    writeln!(self.output, "{:<width$} | {}", a.name, b.name, width = width)?;
+                    writeln!(self.output, "{}", "-".repeat(width * 2 + 3))?;
+                    for i in 0..a_lines.len().max(b_lines.len()) {
+                        let a_line = a_lines.get(i).copied().unwrap_or("");
+                        let b_line = b_lines.get(i).copied().unwrap_or("");
+                        writeln!(self.output, "{:<width$} | {}", a_line, b_line, width = width)?;
+                    }
+                    writeln!(self.output)?;
+                }
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Searches a file and prints each match with surrounding lines
+    fn search_context(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let pattern = self.get_input("Enter search pattern: ")?;
+        let context = self.get_optional_option("--context", "Context lines (default 2): ")?;
+        let context_lines = if context.is_empty() {
+            2
+        } else {
+            context.parse::<usize>()
+                .map_err(|_| FileError::InvalidInput("Context lines must be a number".to_string()))?
+        };
+
+        match self.filesystem.sliding_window_search(&name, &pattern, context_lines) {
+            Ok(results) if results.is_empty() => writeln!(self.output, "📭 No matches for '{}'", pattern)?,
+            Ok(results) => {
+                for result in &results {
+                    let first = result.line_number - result.before.len();
+                    for (i, line) in result.before.iter().enumerate() {
+                        writeln!(self.output, "  {:>4}  {}", first + i, line)?;
+                    }
+                    writeln!(self.output, "> {:>4}  {}", result.line_number, result.matched_line)?;
+                    for (i, line) in result.after.iter().enumerate() {
+                        writeln!(self.output, "  {:>4}  {}", result.line_number + 1 + i, line)?;
+                    }
+                    writeln!(self.output, "--")?;
+                }
+                writeln!(self.output, "🔍 {} matches", results.len())?;
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Shows the readability score of a file
+    fn show_readability(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+
+        match self.filesystem.compute_readability_score(&name) {
+            Ok(readability) => {
+                writeln!(self.output, "{}", self.color.bold(&format!("📖 Readability of '{}':", name)))?;
+                writeln!(self.output, "  Flesch reading ease: {:.1}", readability.score)?;
+                writeln!(self.output, "  Grade level: {}", readability.grade_level)?;
+                writeln!(self.output, "  Words per sentence: {:.1}", readability.avg_words_per_sentence)?;
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Pads a file with a character up to a target size
+    fn pad_file(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let target = self.get_input("Enter target size in bytes: ")?
+            .parse::<usize>()
+            .map_err(|_| FileError::InvalidInput("Target size must be a number".to_string()))?;
+
+        let input = self.get_optional_option("--char", "Pad character (default space): ")?;
+        let mut chars = input.chars();
+        let pad_char = match (chars.next(), chars.next()) {
+            (None, _) => ' ',
+            (Some(c), None) => c,
+            _ => return Err(FileError::InvalidInput("Pad character must be a single character".to_string())),
+        };
+
+        match self.filesystem.pad_to_size(&name, target, pad_char) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' padded to {} bytes", name, target)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Creates a symlink pointing to another file
+    fn create_symlink(&mut self) -> FileResult<()> {
+        let link = self.get_input("Enter link name: ")?;
+        let target = self.get_file_name("Enter target file name: ")?;
+
+        match self.filesystem.create_symlink(link.clone(), &target) {
+            Ok(id) => writeln!(self.output, "{}", self.color.green(&format!("✅ Symlink '{}' → '{}' created with ID: {}", link, target, id)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Creates a hard link to an existing file
+    fn create_hardlink(&mut self) -> FileResult<()> {
+        let existing = self.get_file_path("Enter existing file name: ")?;
+        let name = self.get_input("Enter new link name: ")?;
+
+        match self.filesystem.hardlink_file(&existing, name.clone()) {
+            Ok(id) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' now shares content with '{}' (ID: {})", name, existing, id)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Applies a sequence of transforms to a file
+    fn run_pipeline(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let input = self.get_list("Enter transforms (e.g. trim lowercase wrap:80): ")?;
+
+        let steps = input
+            .iter()
+            .flat_map(|step| step.split_whitespace())
+            .map(TransformStep::parse)
+            .collect::<FileResult<Vec<_>>>()?;
+        let pipeline = FileSystem::content_pipeline(&steps);
+
+        match self.filesystem.apply_pipeline(&name, &pipeline) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ Applied {} transforms to '{}'", steps.len(), name)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Prints all files as an RSS 2.0 feed
+    fn export_rss(&mut self) -> FileResult<()> {
+        let title = self.get_option("--title", "Enter feed title: ")?;
+        let link = self.get_option("--link", "Enter feed link: ")?;
+        let description = self.get_optional_option("--description", "Enter feed description (optional): ")?;
+
+        writeln!(self.output, "{}", self.filesystem.export_rss(&title, &link, &description))?;
+        Ok(())
+    }
+
+    /// Creates files from the RSS feed held in an existing file
+    fn import_rss(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter name of file containing the RSS feed: ")?;
+
+        let xml = match self.filesystem.read_file(&name) {
+            Ok(xml) => xml.to_string(),
+            Err(e) => {
+                self.report_error(&e)?;
+                return Ok(());
+            }
+        };
+
+        match self.filesystem.import_rss(&xml) {
+            Ok(count) => writeln!(self.output, "{}", self.color.green(&format!("✅ Imported {} files from '{}'", count, name)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Prints every text file as versioned JSON
+    fn export_json(&mut self) -> FileResult<()> {
+        writeln!(self.output, "{}", self.filesystem.export_to_json())?;
+        Ok(())
+    }
+
+    /// Creates files from exported JSON held in an existing file
+    fn import_json(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter name of file containing the JSON: ")?;
+
+        let text = match self.filesystem.read_file(&name) {
+            Ok(text) => text.to_string(),
+            Err(e) => {
+                self.report_error(&e)?;
+                return Ok(());
+            }
+        };
+
+        let imported = match FileSystem::import_from_json(&text) {
+            Ok(imported) => imported,
+            Err(e) => {
+                self.report_error(&e)?;
+                return Ok(());
+            }
+        };
+
+        let count = imported.file_count();
+        let errors = self.filesystem.merge(imported).err().map(FileError::into_errors).unwrap_or_default();
+        writeln!(self.output, "{}", self.color.green(&format!("✅ Imported {} files from '{}'", count - errors.len(), name)))?;
+        for e in errors {
+            self.report_error(&e)?;
+        }
+        Ok(())
+    }
+
+    /// Writes every file to a real directory
+    fn export_dir(&mut self) -> FileResult<()> {
+        let dir = self.get_input("Enter directory path: ")?;
+
+        match self.filesystem.export_to_disk(Path::new(&dir)) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ Exported {} files to '{}'", self.filesystem.file_count(), dir)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Creates files from the contents of a real directory
+    fn import_dir(&mut self) -> FileResult<()> {
+        let dir = self.get_input("Enter directory path: ")?;
+
+        let (imported, skipped) = match FileSystem::import_from_disk(Path::new(&dir)) {
+            Ok(imported) => imported,
+            Err(e) => {
+                self.report_error(&e)?;
+                return Ok(());
+            }
+        };
+        for name in skipped {
+            writeln!(self.output, "{}", self.color.yellow(&format!("⚠️  Skipped subdirectory '{}'", name)))?;
+        }
+
+        let count = imported.file_count();
+        let errors = self.filesystem.merge(imported).err().map(FileError::into_errors).unwrap_or_default();
+        writeln!(self.output, "{}", self.color.green(&format!("✅ Imported {} files from '{}'", count - errors.len(), dir)))?;
+        for e in errors {
+            self.report_error(&e)?;
+        }
+        Ok(())
+    }
+
+    /// Shows how file IDs have been allocated
+    fn show_id_report(&mut self) -> FileResult<()> {
+        let report = self.filesystem.generate_id_report();
+
+        writeln!(self.output, "{}", self.color.bold("🔢 ID Report:"))?;
+        writeln!(self.output, "  Next ID: {}", report.next_id)?;
+        writeln!(self.output, "  Allocated: {}", report.allocated)?;
+        writeln!(self.output, "  Highest ID: {}", report.max_id)?;
+        if report.gaps.is_empty() {
+            writeln!(self.output, "  Gaps: none")?;
+        } else {
+            let gaps: Vec<String> = report.gaps.iter().map(|id| id.to_string()).collect();
+            writeln!(self.output, "  Gaps: {}", gaps.join(", "))?;
+        }
+        writeln!(self.output, "  Utilization: {:.0}%", report.utilization * 100.0)?;
+        Ok(())
+    }
+
+    /// Applies heuristic tags to a single file
+    fn auto_tag(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+
+        match self.filesystem.auto_tag(&name) {
+            Ok(tags) if tags.is_empty() => writeln!(self.output, "📭 No new tags for '{}'", name)?,
+            Ok(tags) => writeln!(self.output, "🏷️  Tagged '{}' with: {}", name, tags.join(", "))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Applies heuristic tags to every file
+    fn auto_tag_all(&mut self) -> FileResult<()> {
+        match self.filesystem.auto_tag_all() {
+            Ok(tagged) if tagged.is_empty() => writeln!(self.output, "📭 No new tags added")?,
+            Ok(tagged) => {
+                for (name, tags) in &tagged {
+                    writeln!(self.output, "🏷️  {}: {}", name, tags.join(", "))?;
+                }
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Lists the files that mention a given file name
+    fn cross_reference(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let files = self.filesystem.cross_reference(&name);
+
+        if files.is_empty() {
+            writeln!(self.output, "📭 No files mention '{}'", name)?;
+        } else {
+            writeln!(self.output, "{}", self.color.bold(&format!("🔗 Files mentioning '{}':", name)))?;
+            for file in files {
+                writeln!(self.output, "  {}", self.color.yellow(&file.display_summary()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows the character-wise majority vote of several files
+    fn average_content(&mut self) -> FileResult<()> {
+        let names = self.get_list("Enter file names (space-separated): ")?;
+        let names = names
+            .iter()
+            .map(|path| self.filesystem.resolve_file_path(path))
+            .collect::<FileResult<Vec<String>>>()?;
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        match self.filesystem.compute_average_content(&names) {
+            Ok(average) => {
+                writeln!(self.output, "{}", self.color.bold("🧮 Average content:"))?;
+                writeln!(self.output, "{}", "-".repeat(40))?;
+                writeln!(self.output, "{}", average)?;
+                writeln!(self.output, "{}", "-".repeat(40))?;
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Shows the lines of one file, or of every file, that contain a pattern
+    fn grep_file(&mut self) -> FileResult<()> {
+        let name = self.read_line("Enter file name (blank for all files): ")?;
+        let query = self.get_input("Enter search pattern: ")?;
+
+        if name.is_empty() {
+            let matches = self.filesystem.grep_all(&query);
+            if matches.is_empty() {
+                writeln!(self.output, "📭 No matches for '{}'", query)?;
+            }
+            for (file, line_number, line) in matches {
+                writeln!(self.output, "{}:{}: {}", file, line_number, line)?;
+            }
+            return Ok(());
+        }
+
+        match self.filesystem.search_with_line_numbers(&name, &query) {
+            Ok(matches) if matches.is_empty() => writeln!(self.output, "📭 No matches for '{}' in '{}'", query, name)?,
+            Ok(matches) => {
+                for (line_number, line) in matches {
+                    writeln!(self.output, "{}: {}", line_number, line)?;
+                }
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Searches all files and saves the matching lines to a new file
+    fn grep_collect(&mut self) -> FileResult<()> {
+        let query = self.get_input("Enter search query: ")?;
+        let output = self.get_input("Enter output file name: ")?;
+        let case_sensitive = self.get_switch("--case-sensitive", "Case sensitive? (y/N): ")?;
+
+        match self.filesystem.search_and_collect(&query, output.clone(), case_sensitive) {
+            Ok(id) => writeln!(self.output, "{}", self.color.green(&format!("✅ Results saved to '{}' with ID: {}", output, id)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Starts watching a file; changes are reported after each command
+    fn watch_file(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+
+        let path = self.filesystem.resolve_file_path(&name)?;
+        if self.watchers.iter().any(|w| w.name() == path) {
+            writeln!(self.output, "👀 Already watching '{}'", name)?;
+            return Ok(());
+        }
+
+        match self.filesystem.watch_file(&name) {
+            Ok(watcher) => {
+                self.watchers.push(watcher);
+                writeln!(self.output, "👀 Watching '{}' for changes", name)?;
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Prints diffs for watched files that changed, dropping watchers for files that disappeared
+    fn report_watched_changes(&mut self) -> FileResult<()> {
+        let filesystem = &self.filesystem;
+        let mut report = Vec::new();
+        self.watchers.retain_mut(|watcher| match watcher.diff(filesystem) {
+            Ok(Some(lines)) => {
+                report.push(format!("👀 '{}' changed:", watcher.name()));
+                report.extend(lines.into_iter().map(|line| format!("  {}", line)));
+                true
+            }
+            Ok(None) => true,
+            Err(e) => {
+                report.push(format!("👀 Stopped watching '{}': {}", watcher.name(), e));
+                false
+            }
+        });
+
+        for line in report {
+            writeln!(self.output, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Creates an index file listing all other files
+    fn make_index(&mut self) -> FileResult<()> {
+        let input = self.get_optional("Enter index file name (default _INDEX.txt): ")?;
+        let name = if input.is_empty() { "_INDEX.txt".to_string() } else { input };
+
+        match self.filesystem.create_index_file(name.clone()) {
+            Ok(id) => writeln!(self.output, "{}", self.color.green(&format!("✅ Index '{}' created with ID: {}", name, id)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Adds or removes a tag on a file
+    fn tag_file(&mut self, add: bool) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let tag = self.get_input("Enter tag: ")?;
+
+        let result = if add {
+            self.filesystem.add_tag(&name, &tag)
+        } else {
+            self.filesystem.remove_tag(&name, &tag)
+        };
+
+        match result {
+            Ok(()) if add => writeln!(self.output, "🏷️  Tagged '{}' with '{}'", name, tag.trim().to_lowercase())?,
+            Ok(()) => writeln!(self.output, "🏷️  Removed tag '{}' from '{}'", tag.trim().to_lowercase(), name)?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Locks or unlocks a file
+    fn lock_file(&mut self, lock: bool) -> FileResult<()> {
+        let name = self.get_file_name("Enter file name or ID: ")?;
+
+        // Try to parse as ID first, then as name
+        let result = match name.parse::<u32>() {
+            Ok(id) if lock => self.filesystem.lock_file_by_id(id),
+            Ok(id) => self.filesystem.unlock_file_by_id(id),
+            Err(_) => self.filesystem.resolve_file_path(&name).and_then(|file| {
+                if lock {
+                    self.filesystem.lock_file(&file)
+                } else {
+                    self.filesystem.unlock_file(&file)
+                }
+            }),
+        };
+
+        match result {
+            Ok(()) if lock => writeln!(self.output, "🔒 '{}' is now locked", name)?,
+            Ok(()) => writeln!(self.output, "🔓 '{}' is now unlocked", name)?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Sets a file's read/write/execute permissions
+    fn change_permissions(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let permissions = FilePermissions::parse(&self.get_input("Enter permissions (e.g. rw-): ")?)?;
+
+        match self.filesystem.set_permissions(&name, permissions) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' permissions set to {}", name, permissions)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Freezes or unfreezes the whole filesystem
+    fn set_readonly(&mut self, readonly: bool) -> FileResult<()> {
+        self.filesystem.set_readonly(readonly);
+        if readonly {
+            writeln!(self.output, "🔒 Filesystem is now read-only")?;
+        } else {
+            writeln!(self.output, "🔓 Filesystem is now writable")?;
+        }
+        Ok(())
+    }
+
+    /// Turns case-insensitive name matching on or off
+    fn set_case_insensitive(&mut self, enabled: bool) -> FileResult<()> {
+        self.case_insensitive = enabled;
+        if enabled {
+            writeln!(self.output, "🔤 read, info and delete now match names ignoring case")?;
+        } else {
+            writeln!(self.output, "🔤 read, info and delete now match names exactly")?;
+        }
+        Ok(())
+    }
+
+    /// Turns confirmation before destructive commands on or off
+    fn set_confirm(&mut self, enabled: bool) -> FileResult<()> {
+        self.confirm = enabled;
+        if enabled {
+            writeln!(self.output, "🛡️  delete and truncate will ask for confirmation")?;
+        } else {
+            writeln!(self.output, "🛡️  delete and truncate will no longer ask for confirmation")?;
+        }
+        Ok(())
+    }
+
+    /// Sets how many lines `list` shows before pausing
+    fn set_page_size(&mut self, page_size: usize) -> FileResult<()> {
+        self.page_size = page_size;
+        if page_size == 0 {
+            writeln!(self.output, "📄 list will no longer pause")?;
+        } else {
+            writeln!(self.output, "📄 list will pause every {} lines", page_size)?;
+        }
+        Ok(())
+    }
+
+    /// Prints a unified diff between two files
+    fn diff_files(&mut self) -> FileResult<()> {
+        let old_name = self.get_file_path("Enter original file name: ")?;
+        let new_name = self.get_file_path("Enter changed file name: ")?;
+
+        match self.filesystem.diff(&old_name, &new_name) {
+            Ok(diff) if diff.is_empty() => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' and '{}' are identical", old_name, new_name)))?,
+            Ok(diff) => writeln!(self.output, "{}", diff)?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Lists when each previous version of a file was written
+    fn list_versions(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+
+        match self.filesystem.list_versions(&name) {
+            Ok(versions) if versions.is_empty() => writeln!(self.output, "📭 '{}' has no previous versions", name)?,
+            Ok(versions) => {
+                writeln!(self.output, "{}", self.color.bold(&format!("🕘 Versions of '{}':", name)))?;
+                for (index, time) in versions.iter().enumerate() {
+                    let age = time.elapsed().unwrap_or_default();
+                    writeln!(self.output, "  {}: written {:?} ago", index, age)?;
+                }
+            }
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Restores a previous version of a file
+    fn revert_file(&mut self) -> FileResult<()> {
+        let name = self.get_file_path("Enter file name: ")?;
+        let version = self.get_input("Enter version (0 = most recent): ")?
+            .parse::<usize>()
+            .map_err(|_| FileError::InvalidInput("Version must be a non-negative number".to_string()))?;
+
+        match self.filesystem.revert_to_version(&name, version) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ '{}' reverted to version {}", name, version)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Creates a directory
+    fn make_directory(&mut self) -> FileResult<()> {
+        let path = self.get_input("Enter directory path: ")?;
+
+        match self.filesystem.mkdir(&path) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ Directory '{}' created", path)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Removes a directory, optionally with everything inside it
+    fn remove_directory(&mut self) -> FileResult<()> {
+        let path = self.get_input("Enter directory path: ")?;
+        let recursive = self.get_switch("--recursive", "Remove everything inside it too? (y/N): ")?;
+
+        match self.filesystem.rmdir(&path, recursive) {
+            Ok(()) => writeln!(self.output, "{}", self.color.green(&format!("✅ Directory '{}' removed", path)))?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Changes the current directory
+    fn change_directory(&mut self) -> FileResult<()> {
+        let path = self.get_input("Enter directory path: ")?;
+
+        match self.filesystem.cd(&path) {
+            Ok(()) => writeln!(self.output, "📁 {}", self.filesystem.pwd())?,
+            Err(e) => self.report_error(&e)?,
+        }
+        Ok(())
+    }
+
+    /// Lists groups of files with identical content
+    fn show_duplicates(&mut self) -> FileResult<()> {
+        let groups = self.filesystem.find_duplicates();
+
+        if groups.is_empty() {
+            writeln!(self.output, "{}", self.color.green("✅ No duplicate files found"))?;
+            return Ok(());
+        }
+
+        writeln!(self.output, "{}", self.color.bold("🧬 Duplicate groups:"))?;
+        for ids in &groups {
+            let names: Vec<String> = ids
+                .iter()
+                .filter_map(|id| self.filesystem.get_file_by_id(*id).ok())
+                .map(|f| format!("[{}] {}", f.id, f.name))
+                .collect();
+            writeln!(self.output, "  {}", names.join(", "))?;
+        }
+        Ok(())
+    }
+
+    /// Prompts for the path of an existing file, relative to the current directory
+    fn get_file_path(&mut self, prompt: &str) -> FileResult<String> {
+        let path = self.get_file_name(prompt)?;
+        self.filesystem.resolve_file_path(&path)?;
+        Ok(path)
+    }
+
+    /// Resolves a typed file path, falling back to a case-insensitive match on the
+    /// file name when that mode is on and there is no exact match
+    fn resolve_name(&self, input: &str) -> FileResult<String> {
+        match self.filesystem.resolve_file_path(input) {
+            Ok(_) => Ok(input.to_string()),
+            Err(FileError::NotFound(_)) if self.case_insensitive => {
+                match self.filesystem.find_case_insensitive(input).as_slice() {
+                    [] => Err(FileError::NotFound(input.to_string())),
+                    [file] => Ok(match input.rsplit_once('/') {
+                        Some((dir, _)) => format!("{}/{}", dir, file.name),
+                        None => file.name.clone(),
+                    }),
+                    matches => {
+                        let names: Vec<&str> = matches.iter().map(|f| f.name.as_str()).collect();
+                        Err(FileError::InvalidInput(format!(
+                            "'{}' matches several files: {}",
+                            input,
+                            names.join(", ")
+                        )))
+                    }
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// Gets a file name, or anything else that identifies a file, with a prompt.
+    /// At a terminal, Tab completes the names of files in the current directory.
+    fn get_file_name(&mut self, prompt: &str) -> FileResult<String> {
+        if !self.tab_completion || !self.pending_args.is_empty() {
+            return self.get_input(prompt);
+        }
+
+        let (_, entries) = self.filesystem.list_cwd();
+        let names: Vec<String> = entries.iter().map(|entry| entry.name().to_string()).collect();
+        let input = self.readline_with_completion(prompt, &names)?;
+        if input.is_empty() {
+            return Err(FileError::InvalidInput("Input cannot be empty".to_string()));
+        }
+
+        Ok(input)
+    }
+
+    /// Reads a trimmed line from the terminal, completing it from `completions` on
+    /// Tab. A single match is filled in; several matches are listed below the prompt
+    /// and the line is filled in up to their common prefix.
+    #[cfg(unix)]
+    fn readline_with_completion(&mut self, prompt: &str, completions: &[String]) -> FileResult<String> {
+        use std::io::Read;
+
+        write!(self.output, "{}", prompt)?;
+        self.output.flush()?;
+
+        let raw = terminal::RawMode::enable()?;
+        let mut stdin = io::stdin().lock();
+        let mut line = String::new();
+        // Bytes of a multi-byte character that hasn't fully arrived yet
+        let mut partial = Vec::new();
+        let mut byte = [0; 1];
+
+        loop {
+            if stdin.read(&mut byte)? == 0 {
+                return Err(end_of_input());
+            }
+
+            match byte[0] {
+                b'\r' | b'\n' => {
+                    writeln!(self.output)?;
+                    break;
+                }
+                // Ctrl-D on an empty line
+                4 if line.is_empty() => {
+                    writeln!(self.output)?;
+                    return Err(end_of_input());
+                }
+                // Backspace and Delete
+                8 | 127 => {
+                    if line.pop().is_some() {
+                        write!(self.output, "\x08 \x08")?;
+                    }
+                }
+                b'\t' => {
+                    let matches: Vec<&String> = completions.iter().filter(|c| c.starts_with(&line)).collect();
+                    match matches.as_slice() {
+                        [] => {}
+                        [only] => {
+                            write!(self.output, "{}", &only[line.len()..])?;
+                            line = only.to_string();
+                        }
+                        [first, rest @ ..] => {
+                            let prefix = rest.iter().fold(first.as_str(), |prefix, c| terminal::common_prefix(prefix, c));
+                            line = prefix.to_string();
+                            let names: Vec<&str> = matches.iter().map(|c| c.as_str()).collect();
+                            write!(self.output, "\n{}\n{}{}", names.join("  "), prompt, line)?;
+                        }
+                    }
+                }
+                // Skip escape sequences such as the arrow keys. A lone Esc has nothing
+                // after it, so the rest is read with a timeout instead of blocking.
+                0x1b => {
+                    let mut sequence = [0; 2];
+                    raw.with_timeout(|| {
+                        let mut filled = 0;
+                        while filled < sequence.len() {
+                            match stdin.read(&mut sequence[filled..])? {
+                                0 => break,
+                                n => filled += n,
+                            }
+                        }
+                        Ok(())
+                    })?;
+                }
+                b if b < 0x20 => {}
+                b => {
+                    partial.push(b);
+                    if let Ok(text) = std::str::from_utf8(&partial) {
+                        write!(self.output, "{}", text)?;
+                        line.push_str(text);
+                        partial.clear();
+                    } else if partial.len() >= 4 {
+                        partial.clear();
+                    }
+                }
+            }
+            self.output.flush()?;
+        }
+
+        Ok(line.trim().to_string())
+    }
+
+    /// Reads a trimmed line without completion where raw terminal input isn't supported
+    #[cfg(not(unix))]
+    fn readline_with_completion(&mut self, prompt: &str, _completions: &[String]) -> FileResult<String> {
+        self.read_line(prompt)
+    }
+
+    /// Gets user input with a prompt
+    fn get_input(&mut self, prompt: &str) -> FileResult<String> {
+        let trimmed = self.read_line(prompt)?;
+        if trimmed.is_empty() {
+            return Err(FileError::InvalidInput("Input cannot be empty".to_string()));
+        }
+
+        Ok(trimmed)
+    }
+
+    /// Gets content with a prompt. An answer of `<<EOF`, or `<<` followed by any other
+    /// delimiter, reads the lines that follow up to one holding only the delimiter and
+    /// joins them with '\n'.
+    fn get_multiline_input(&mut self, prompt: &str) -> FileResult<String> {
+        let first = self.get_input(prompt)?;
+        self.read_heredoc(first)
+    }
+
+    /// Like `get_multiline_input`, but an empty answer is allowed
+    fn get_optional_multiline_input(&mut self, prompt: &str) -> FileResult<String> {
+        let first = self.read_line(prompt)?;
+        self.read_heredoc(first)
+    }
+
+    /// Returns `first` unchanged unless it starts a heredoc, in which case the lines
+    /// up to the delimiter are read and returned instead
+    fn read_heredoc(&mut self, first: String) -> FileResult<String> {
+        let delimiter = match first.strip_prefix("<<") {
+            Some(delimiter) if !delimiter.trim().is_empty() => delimiter.trim().to_string(),
+            _ => return Ok(first),
+        };
+
+        let mut lines = Vec::new();
+        loop {
+            write!(self.output, "> ")?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Err(FileError::InvalidInput(format!("Missing closing '{}' line", delimiter)));
+            }
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.trim() == delimiter {
+                return Ok(lines.join("\n"));
+            }
+            lines.push(line.to_string());
+        }
+    }
+
+    /// Asks before a destructive operation, answering yes without asking once
+    /// confirmations are turned off or when `--force` was given
+    fn ask_confirm(&mut self, prompt: &str) -> FileResult<bool> {
+        if !self.confirm || self.pending_flags.remove("--force").is_some() {
+            return Ok(true);
+        }
+        self.get_flag(&format!("{} [y/N]: ", prompt))
+    }
+
+    /// Asks a yes/no question, treating anything but 'y' or 'yes' as no
+    fn get_flag(&mut self, prompt: &str) -> FileResult<bool> {
+        let answer = self.read_line(prompt)?.to_lowercase();
+        Ok(answer == "y" || answer == "yes")
+    }
+
+    /// Whether the switch `flag` was given. A command typed without inline arguments
+    /// asks the yes/no question instead.
+    fn get_switch(&mut self, flag: &str, prompt: &str) -> FileResult<bool> {
+        if self.pending_flags.remove(flag).is_some() {
+            return Ok(true);
+        }
+        if self.inline {
+            return Ok(false);
+        }
+        self.get_flag(prompt)
+    }
+
+    /// The value given for the option `flag`, or else the next inline argument or
+    /// an answer to the prompt
+    fn get_option(&mut self, flag: &str, prompt: &str) -> FileResult<String> {
+        match self.pending_flags.remove(flag) {
+            Some(Some(value)) => Ok(value),
+            _ => self.get_input(prompt),
+        }
+    }
+
+    /// Like `get_optional`, preferring the value given for the option `flag`
+    fn get_optional_option(&mut self, flag: &str, prompt: &str) -> FileResult<String> {
+        match self.pending_flags.remove(flag) {
+            Some(value) => Ok(value.unwrap_or_default()),
+            None => self.get_optional(prompt),
+        }
+    }
+
+    /// Reads an answer that may be left empty for a default. A command given inline
+    /// arguments takes the default once they run out instead of asking.
+    fn get_optional(&mut self, prompt: &str) -> FileResult<String> {
+        if self.inline && self.pending_args.is_empty() {
+            return Ok(String::new());
+        }
+        self.read_line(prompt)
+    }
+
+    /// Takes every remaining inline argument as a list, or else splits the answer to
+    /// the prompt on whitespace
+    fn get_list(&mut self, prompt: &str) -> FileResult<Vec<String>> {
+        if !self.pending_args.is_empty() {
+            return Ok(self.pending_args.drain(..).collect());
+        }
+        Ok(self.get_input(prompt)?.split_whitespace().map(str::to_string).collect())
+    }
+
+    /// Reads a trimmed line of user input, which may be empty.
+    /// The next inline argument is used instead, if there is one.
+    fn read_line(&mut self, prompt: &str) -> FileResult<String> {
+        if let Some(arg) = self.pending_args.pop_front() {
+            return Ok(arg);
+        }
+
+        write!(self.output, "{}", prompt)?;
+        self.output.flush()?;
+
+        let mut input = String::new();
+        if self.input.read_line(&mut input)? == 0 {
+            return Err(end_of_input());
+        }
+
+        Ok(input.trim().to_string())
+    }
+}
+
+/// The error for input that ran out before a command finished reading it
+fn end_of_input() -> FileError {
+    FileError::from(io::Error::new(io::ErrorKind::UnexpectedEof, "Unexpected end of input"))
+}
+
+/// Where command history is kept between sessions: `~/.file_cli_history`
+fn history_file() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".file_cli_history"))
 }
 
-impl Default for CLI {
+impl Default for CLI<'_> {
     fn default() -> Self {
         Self::new()
     }
@@ -0,0 +1,116 @@
+use std::fmt;
+
+/// One line of a line-based diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+impl fmt::Display for DiffLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffLine::Same(line) => write!(f, " {}", line),
+            DiffLine::Added(line) => write!(f, "+{}", line),
+            DiffLine::Removed(line) => write!(f, "-{}", line),
+        }
+    }
+}
+
+/// Diffs two texts line by line using the longest common subsequence
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|l| DiffLine::Removed(l.to_string())));
+    lines.extend(new[j..].iter().map(|l| DiffLine::Added(l.to_string())));
+
+    lines
+}
+
+/// Renders the changes between two texts as a unified diff, keeping `context`
+/// unchanged lines around each hunk. Returns an empty string if they match.
+pub fn unified_diff(old_name: &str, new_name: &str, old: &str, new: &str, context: usize) -> String {
+    let lines = diff_lines(old, new);
+
+    // Line number in each text *before* every diff line (0-based)
+    let mut positions = Vec::with_capacity(lines.len());
+    let (mut old_line, mut new_line) = (0, 0);
+    for line in &lines {
+        positions.push((old_line, new_line));
+        match line {
+            DiffLine::Same(_) => {
+                old_line += 1;
+                new_line += 1;
+            }
+            DiffLine::Removed(_) => old_line += 1,
+            DiffLine::Added(_) => new_line += 1,
+        }
+    }
+
+    // Group changed lines into hunks, merging changes separated by little context
+    let changes: Vec<usize> = (0..lines.len())
+        .filter(|&i| !matches!(lines[i], DiffLine::Same(_)))
+        .collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changes {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let mut output = vec![format!("--- {}", old_name), format!("+++ {}", new_name)];
+    for (start, end) in hunks {
+        let hunk = &lines[start..end];
+        let old_count = hunk.iter().filter(|l| !matches!(l, DiffLine::Added(_))).count();
+        let new_count = hunk.iter().filter(|l| !matches!(l, DiffLine::Removed(_))).count();
+        let (old_start, new_start) = positions[start];
+
+        // Unified diff numbers lines from 1, and uses the preceding line for empty ranges
+        let range_start = |line: usize, count: usize| if count == 0 { line } else { line + 1 };
+        output.push(format!(
+            "@@ -{},{} +{},{} @@",
+            range_start(old_start, old_count),
+            old_count,
+            range_start(new_start, new_count),
+            new_count
+        ));
+        output.extend(hunk.iter().map(DiffLine::to_string));
+    }
+    output.join("\n")
+}
@@ -1,6 +1,9 @@
 use std::fmt;
+use std::sync::Arc;
 
-/// Custom error types for the file management system
+/// Custom error types for the file management system. Variants that wrap another
+/// error include its message in their `Display` output and also return it from
+/// `source()`, so printing an error shows the whole chain.
 #[derive(Debug, Clone)]
 pub enum FileError {
     NotFound(String),
@@ -9,6 +12,20 @@ pub enum FileError {
     AccessDenied(String),
     EmptyContent,
     InvalidId(u32),
+    /// A write would push the filesystem's total size past its limit
+    SizeLimitExceeded { attempted: usize, limit: usize },
+    /// The filesystem already holds its maximum number of files
+    QuotaExceeded(usize),
+    /// Another error annotated with where it happened
+    Contextual { context: String, source: Box<FileError> },
+    /// An error from outside this crate, kept intact for `source()`.
+    /// Shared rather than boxed so `FileError` stays `Clone`.
+    Wrapped(Arc<dyn std::error::Error + Send + Sync>),
+    /// Several operations in a bulk call failed
+    BatchError(Vec<FileError>),
+    /// An I/O failure, stored as its message
+    #[deprecated(note = "`io::Error` now converts to `Wrapped`, which keeps the original error as the `source()`")]
+    Io(String),
 }
 
 impl fmt::Display for FileError {
@@ -20,11 +37,72 @@ impl fmt::Display for FileError {
             FileError::AccessDenied(msg) => write!(f, "Access denied: {}", msg),
             FileError::EmptyContent => write!(f, "Cannot create file with empty content"),
             FileError::InvalidId(id) => write!(f, "Invalid file ID: {}", id),
+            FileError::SizeLimitExceeded { attempted, limit } => write!(
+                f,
+                "Size limit exceeded: storing this would use {} bytes but the limit is {} bytes",
+                attempted, limit
+            ),
+            FileError::QuotaExceeded(max) => write!(f, "File limit of {} reached", max),
+            FileError::Contextual { context, source } => write!(f, "{}: {}", context, source),
+            FileError::Wrapped(error) if error.is::<std::io::Error>() => write!(f, "I/O error: {}", error),
+            FileError::Wrapped(error) => write!(f, "External error: {}", error),
+            FileError::BatchError(errors) => {
+                write!(f, "{} operations failed:", errors.len())?;
+                for (i, error) in errors.iter().enumerate() {
+                    write!(f, "\n  {}. {}", i + 1, error)?;
+                }
+                Ok(())
+            }
+            #[allow(deprecated)]
+            FileError::Io(msg) => write!(f, "I/O error: {}", msg),
         }
     }
 }
 
-impl std::error::Error for FileError {}
+impl FileError {
+    /// Wraps this error with a breadcrumb describing what was being attempted
+    pub fn with_context(self, context: impl Into<String>) -> Self {
+        FileError::Contextual {
+            context: context.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Renders the error for a user, which `Display` now does in full
+    #[deprecated(note = "`Display` includes the messages of wrapped errors; use `to_string()`")]
+    pub fn report(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns true for `BatchError`
+    pub fn is_batch(&self) -> bool {
+        matches!(self, FileError::BatchError(_))
+    }
+
+    /// Unpacks a `BatchError` into its errors; any other error becomes a single-item list
+    pub fn into_errors(self) -> Vec<FileError> {
+        match self {
+            FileError::BatchError(errors) => errors,
+            error => vec![error],
+        }
+    }
+}
+
+impl std::error::Error for FileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FileError::Contextual { source, .. } => Some(source.as_ref()),
+            FileError::Wrapped(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for FileError {
+    fn from(error: std::io::Error) -> Self {
+        FileError::Wrapped(Arc::new(error))
+    }
+}
 
 /// Result type alias for file operations
 pub type FileResult<T> = Result<T, FileError>;
@@ -1,14 +1,125 @@
+use crate::diff::{self, DiffLine};
 use crate::error::{FileError, FileResult};
+use crate::json::{self, JsonValue};
+use crate::pipeline::{ContentPipeline, TransformStep};
+use crate::rss;
+use crate::snapshot::{self, BinaryRecord, Snapshot, SnapshotRecord};
+use crate::stats::{self, FileStats, IdReport, StatsReport};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::hash_map::DefaultHasher;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Number of characters shown by `File::preview_default`
+pub const DEFAULT_PREVIEW_CHARS: usize = 80;
+
+/// Number of previous versions a file keeps unless configured otherwise
+pub const DEFAULT_MAX_VERSIONS: usize = 5;
+
+/// Unchanged lines shown around each hunk by `FileSystem::diff`
+pub const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Version of the JSON format written by `FileSystem::export_to_json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaVersion {
+    /// A bare array of files holding `id`, `name`, `content`, `size` and `created_at`
+    V1 = 1,
+    /// `{"version":2,"files":[...],"directories":[...]}`, adding `modified_at`, `tags`,
+    /// `symlink_target`, each file's `directory` and the directories themselves
+    V2 = 2,
+}
+
+impl SchemaVersion {
+    /// The version written by `export_to_json`
+    pub const CURRENT: SchemaVersion = SchemaVersion::V2;
+
+    /// Looks up the version stored in a saved state header
+    pub fn from_number(number: u64) -> FileResult<Self> {
+        match number {
+            1 => Ok(SchemaVersion::V1),
+            2 => Ok(SchemaVersion::V2),
+            _ => Err(FileError::InvalidInput(format!("Unsupported schema version {}", number))),
+        }
+    }
+
+    pub fn number(self) -> u64 {
+        self as u64
+    }
+}
+
+/// Unix-style permission flags for a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilePermissions {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl FilePermissions {
+    /// Parses an `ls`-style mode such as `rw-` or `r-x`
+    pub fn parse(mode: &str) -> FileResult<Self> {
+        let invalid = || FileError::InvalidInput(format!("Invalid permissions '{}', expected e.g. rw- or r-x", mode));
+        let flags: Vec<char> = mode.chars().collect();
+        let [read, write, execute] = flags.as_slice() else {
+            return Err(invalid());
+        };
+
+        let flag = |c: char, set: char| match c {
+            '-' => Ok(false),
+            c if c == set => Ok(true),
+            _ => Err(invalid()),
+        };
+        Ok(FilePermissions {
+            read: flag(*read, 'r')?,
+            write: flag(*write, 'w')?,
+            execute: flag(*execute, 'x')?,
+        })
+    }
+}
+
+impl Default for FilePermissions {
+    fn default() -> Self {
+        FilePermissions { read: true, write: true, execute: true }
+    }
+}
+
+impl fmt::Display for FilePermissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            if self.read { 'r' } else { '-' },
+            if self.write { 'w' } else { '-' },
+            if self.execute { 'x' } else { '-' }
+        )
+    }
+}
 
 /// Represents a file in memory
 #[derive(Debug, Clone)]
 pub struct File {
     pub id: u32,
     pub name: String,
-    pub content: String,
+    /// Shared so hard links can point at the same content; writes replace the `Arc` (copy-on-write)
+    pub content: Arc<String>,
     pub size: usize,
     pub created_at: std::time::SystemTime,
+    pub modified_at: std::time::SystemTime,
+    pub accessed_at: std::time::SystemTime,
+    pub is_symlink: bool,
+    pub symlink_target: Option<String>,
+    pub tags: Vec<String>,
+    pub locked: bool,
+    pub permissions: FilePermissions,
+    /// Previous contents with the time each was written, most recent first
+    pub versions: Vec<(String, std::time::SystemTime)>,
+    /// How many previous versions to keep
+    pub max_versions: usize,
+    /// User who owns the file; empty when unowned
+    pub owner: String,
 }
 
 impl File {
@@ -24,30 +135,169 @@ impl File {
         Ok(File {
             id,
             name,
-            content,
+            content: Arc::new(content),
             size,
             created_at,
+            modified_at: created_at,
+            accessed_at: created_at,
+            is_symlink: false,
+            symlink_target: None,
+            tags: Vec::new(),
+            locked: false,
+            permissions: FilePermissions::default(),
+            versions: Vec::new(),
+            max_versions: DEFAULT_MAX_VERSIONS,
+            owner: String::new(),
         })
     }
 
-    /// Updates the file content
+    /// Creates a symlink entry pointing at another file name
+    pub fn new_symlink(id: u32, name: String, target: String) -> FileResult<Self> {
+        let mut file = File::new(id, name, String::new())?;
+        file.is_symlink = true;
+        file.symlink_target = Some(target);
+        Ok(file)
+    }
+
+    /// Recomputes `size` from `content`, for callers that replaced `content` directly
+    pub fn sync_size(&mut self) {
+        self.size = self.content.len();
+    }
+
+    /// Updates the file content and bumps `modified_at`, keeping the old content as a version
     pub fn write_content(&mut self, content: String) {
-        self.content = content;
+        let previous = std::mem::replace(&mut self.content, Arc::new(content));
+        if self.max_versions > 0 {
+            self.versions.insert(0, (previous.to_string(), self.modified_at));
+            self.versions.truncate(self.max_versions);
+        }
         self.size = self.content.len();
+        self.modified_at = std::time::SystemTime::now();
     }
 
-    /// Gets a preview of the file content (first 50 characters)
-    pub fn preview(&self) -> &str {
-        if self.content.len() > 50 {
-            &self.content[..50]
-        } else {
-            &self.content
+    /// Gets a preview of the file content (first `max_chars` characters),
+    /// never splitting a multi-byte character
+    pub fn preview(&self, max_chars: usize) -> &str {
+        match self.content.char_indices().nth(max_chars) {
+            Some((end, _)) => &self.content[..end],
+            None => &self.content,
+        }
+    }
+
+    /// Gets a preview of the first `DEFAULT_PREVIEW_CHARS` characters
+    pub fn preview_default(&self) -> &str {
+        self.preview(DEFAULT_PREVIEW_CHARS)
+    }
+
+    /// Gets a lowercase hex fingerprint of the content.
+    /// Uses the standard library's SipHash, so values are only stable within one build.
+    pub fn checksum(&self) -> String {
+        format!("{:016x}", content_hash(&self.content))
+    }
+
+    /// Checks whether the file has no content
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// Iterates over the content's lines
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.content.lines()
+    }
+
+    /// Counts lines; a final line without a trailing newline still counts, and empty content has 0 lines
+    pub fn line_count(&self) -> usize {
+        self.lines().count()
+    }
+
+    /// Counts whitespace-separated words
+    pub fn word_count(&self) -> usize {
+        self.content.split_ascii_whitespace().count()
+    }
+
+    /// Returns `AccessDenied` if the file is locked or lacks write permission
+    pub fn check_writable(&self) -> FileResult<()> {
+        if self.locked {
+            return Err(FileError::AccessDenied(format!("File '{}' is locked", self.name)));
+        }
+        self.check_write_permission()
+    }
+
+    /// Returns `AccessDenied` if the file lacks write permission
+    pub fn check_write_permission(&self) -> FileResult<()> {
+        if !self.permissions.write {
+            return Err(FileError::AccessDenied(format!("File '{}' is not writable", self.name)));
+        }
+        Ok(())
+    }
+
+    /// Returns `AccessDenied` if the file lacks read permission
+    pub fn check_read_permission(&self) -> FileResult<()> {
+        if !self.permissions.read {
+            return Err(FileError::AccessDenied(format!("File '{}' is not readable", self.name)));
+        }
+        Ok(())
+    }
+
+    /// Suggests tags for this file based on its extension and content
+    pub fn suggested_tags(&self) -> Vec<&'static str> {
+        let mut tags = Vec::new();
+
+        match self.extension() {
+            Some("md") | Some("txt") => tags.push("text"),
+            Some("csv") => tags.push("csv"),
+            Some("log") => tags.push("log"),
+            Some("rs") | Some("py") | Some("js") | Some("c") => tags.push("code"),
+            _ => {}
+        }
+        if self.content.contains("{\"") {
+            tags.push("json");
+        }
+        if self.content.to_lowercase().contains("<html") {
+            tags.push("html");
         }
+        if self.content.chars().any(|c| c.is_alphabetic()) && !self.content.chars().any(|c| c.is_lowercase()) {
+            tags.push("shout");
+        }
+        if self.content.is_empty() {
+            tags.push("empty");
+        }
+
+        tags
     }
 
-    /// Gets the file extension
+    /// Gets the file extension, or `None` if the name has no dot
     pub fn extension(&self) -> Option<&str> {
-        self.name.split('.').last()
+        self.name.rsplit_once('.').map(|(_, ext)| ext)
+    }
+
+    /// Renders the file as a JSON object; `created_at` and `modified_at` are in seconds
+    /// since the Unix epoch and `symlink_target` is null for regular files
+    pub fn to_json(&self) -> String {
+        format!("{{{}}}", self.json_fields())
+    }
+
+    /// The members of the `to_json` object, without the surrounding braces
+    fn json_fields(&self) -> String {
+        let secs = |time: std::time::SystemTime| {
+            time.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+        };
+        let tags: Vec<String> = self.tags.iter().map(|t| format!("\"{}\"", stats::escape_json(t))).collect();
+        let symlink_target = match &self.symlink_target {
+            Some(target) => format!("\"{}\"", stats::escape_json(target)),
+            None => "null".to_string(),
+        };
+        format!(
+            "\"id\":{},\"name\":\"{}\",\"content\":\"{}\",\"size\":{},\"created_at\":{},\"modified_at\":{},\"tags\":[{}],\"symlink_target\":{}",
+            self.id,
+            stats::escape_json(&self.name),
+            stats::escape_json(&self.content),
+            self.size,
+            secs(self.created_at),
+            secs(self.modified_at),
+            tags.join(","),
+            symlink_target
+        )
     }
 }
 
@@ -59,22 +309,77 @@ pub trait FileDisplay {
 
 impl FileDisplay for File {
     fn display_summary(&self) -> String {
-        format!("[{}] {} ({} bytes)", self.id, self.name, self.size)
+        let lock = if self.locked { " 🔒" } else { "" };
+        format!("[{}] {}{} ({} bytes)", self.id, self.name, lock, self.size)
     }
 
     fn display_detailed(&self) -> String {
         let elapsed = self.created_at.elapsed()
             .unwrap_or_else(|_| std::time::Duration::new(0, 0));
+        let modified = self.modified_at.elapsed()
+            .unwrap_or_else(|_| std::time::Duration::new(0, 0));
+        let accessed = self.accessed_at.elapsed()
+            .unwrap_or_else(|_| std::time::Duration::new(0, 0));
         
-        format!(
-            "ID: {}\nName: {}\nSize: {} bytes\nCreated: {:?} ago\nPreview: {}{}",
+        let mut details = format!(
+            "ID: {}\nName: {}\nSize: {} bytes\nPermissions: {}\nCreated: {:?} ago\nLast modified: {:?} ago\nLast accessed: {:?} ago\nPreview: {}{}",
             self.id,
             self.name,
             self.size,
+            self.permissions,
             elapsed,
-            self.preview(),
-            if self.content.len() > 50 { "..." } else { "" }
-        )
+            modified,
+            accessed,
+            self.preview_default(),
+            if self.preview_default().len() < self.content.len() { "..." } else { "" }
+        );
+
+        let owner = if self.owner.is_empty() { "(none)" } else { &self.owner };
+        details.push_str(&format!("\nOwner: {}", owner));
+        if !self.tags.is_empty() {
+            details.push_str(&format!("\nTags: {}", self.tags.join(", ")));
+        }
+        if let Some(target) = &self.symlink_target {
+            details.push_str(&format!("\nSymlink → {}", target));
+        }
+
+        details
+    }
+}
+
+/// Files are equal when their name and content match; IDs and timestamps are ignored
+impl PartialEq for File {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.content == other.content
+    }
+}
+
+impl Eq for File {}
+
+/// Files order by size, then name. Content is the final tiebreaker so ordering agrees with `Eq`.
+impl Ord for File {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.size
+            .cmp(&other.size)
+            .then_with(|| self.name.cmp(&other.name))
+            .then_with(|| self.content.cmp(&other.content))
+    }
+}
+
+impl PartialOrd for File {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Hashes only the `id`, which is unique within a `FileSystem`.
+///
+/// Note that this is inconsistent with `Eq`, which compares name and content: two equal
+/// files from different filesystems may hash differently. Use `FileById` when you need
+/// hashing and equality that agree.
+impl Hash for File {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
     }
 }
 
@@ -84,109 +389,3064 @@ impl fmt::Display for File {
     }
 }
 
-/// In-memory file system manager
-pub struct FileSystem {
-    files: Vec<File>,
-    next_id: u32,
+/// Wraps a `File` so that both `Hash` and `Eq` use the ID alone, for keys like `HashMap<FileById, T>`
+#[derive(Debug, Clone)]
+pub struct FileById(pub File);
+
+impl PartialEq for FileById {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id == other.0.id
+    }
 }
 
-impl FileSystem {
-    pub fn new() -> Self {
-        FileSystem {
-            files: Vec::new(),
-            next_id: 1,
-        }
+impl Eq for FileById {}
+
+impl Hash for FileById {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.id.hash(state);
+    }
+}
+
+/// Direction for sorted listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Key used by `sort_files` and `sorted_files`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortField {
+    Name,
+    Size,
+    Id,
+    Created,
+}
+
+impl SortField {
+    /// Compares two files by this field, falling back to ID so the order is total
+    fn compare(self, a: &File, b: &File) -> std::cmp::Ordering {
+        let ordering = match self {
+            SortField::Name => a.name.cmp(&b.name),
+            SortField::Size => a.size.cmp(&b.size),
+            SortField::Id => std::cmp::Ordering::Equal,
+            SortField::Created => a.created_at.cmp(&b.created_at),
+        };
+        ordering.then(a.id.cmp(&b.id))
     }
+}
 
-    /// Creates a new file
-    pub fn create_file(&mut self, name: String, content: String) -> FileResult<u32> {
-        // Check if file already exists
-        if self.files.iter().any(|f| f.name == name) {
-            return Err(FileError::AlreadyExists(name));
+/// A file holding arbitrary bytes rather than UTF-8 text
+#[derive(Debug, Clone)]
+pub struct BinaryFile {
+    pub id: u32,
+    pub name: String,
+    pub content: Vec<u8>,
+    pub size: usize,
+    pub created_at: std::time::SystemTime,
+    pub modified_at: std::time::SystemTime,
+}
+
+impl BinaryFile {
+    /// Creates a new binary file with the given name and data
+    pub fn new(id: u32, name: String, content: Vec<u8>) -> FileResult<Self> {
+        if name.trim().is_empty() {
+            return Err(FileError::InvalidInput("File name cannot be empty".to_string()));
         }
 
-        let id = self.next_id;
-        let file = File::new(id, name, content)?;
-        self.files.push(file);
-        self.next_id += 1;
-        Ok(id)
+        let created_at = std::time::SystemTime::now();
+        Ok(BinaryFile {
+            id,
+            name,
+            size: content.len(),
+            content,
+            created_at,
+            modified_at: created_at,
+        })
     }
 
-    /// Writes content to an existing file
-    pub fn write_file(&mut self, name: &str, content: String) -> FileResult<()> {
-        match self.files.iter_mut().find(|f| f.name == name) {
-            Some(file) => {
-                file.write_content(content);
-                Ok(())
-            }
-            None => Err(FileError::NotFound(name.to_string())),
+    /// Replaces the data and bumps `modified_at`
+    pub fn write_content(&mut self, content: Vec<u8>) {
+        self.size = content.len();
+        self.content = content;
+        self.modified_at = std::time::SystemTime::now();
+    }
+}
+
+impl FileDisplay for BinaryFile {
+    fn display_summary(&self) -> String {
+        format!("[{}] {} ({} bytes)", self.id, self.name, self.size)
+    }
+
+    fn display_detailed(&self) -> String {
+        let elapsed = self.created_at.elapsed()
+            .unwrap_or_else(|_| std::time::Duration::new(0, 0));
+        let hex: Vec<String> = self.content.iter().take(16).map(|b| format!("{:02x}", b)).collect();
+
+        format!(
+            "ID: {}\nName: {}\nSize: {} bytes\nCreated: {:?} ago\nPreview: {}{}",
+            self.id,
+            self.name,
+            self.size,
+            elapsed,
+            hex.join(" "),
+            if self.content.len() > 16 { " ..." } else { "" }
+        )
+    }
+}
+
+/// A borrowed view of either kind of file, as returned by `FileSystem::entries`
+#[derive(Debug, Clone, Copy)]
+pub enum FileEntry<'a> {
+    Text(&'a File),
+    Binary(&'a BinaryFile),
+}
+
+impl<'a> FileEntry<'a> {
+    pub fn id(&self) -> u32 {
+        match self {
+            FileEntry::Text(f) => f.id,
+            FileEntry::Binary(f) => f.id,
         }
     }
 
-    /// Reads a file's content
-    pub fn read_file(&self, name: &str) -> FileResult<&str> {
-        match self.files.iter().find(|f| f.name == name) {
-            Some(file) => Ok(&file.content),
-            None => Err(FileError::NotFound(name.to_string())),
+    pub fn name(&self) -> &'a str {
+        match self {
+            FileEntry::Text(f) => &f.name,
+            FileEntry::Binary(f) => &f.name,
         }
     }
 
-    /// Gets a file by name
-    pub fn get_file(&self, name: &str) -> FileResult<&File> {
-        match self.files.iter().find(|f| f.name == name) {
-            Some(file) => Ok(file),
-            None => Err(FileError::NotFound(name.to_string())),
+    /// Gets a short `[txt]`/`[bin]` marker for listings
+    pub fn kind(&self) -> &'static str {
+        match self {
+            FileEntry::Text(_) => "[txt]",
+            FileEntry::Binary(_) => "[bin]",
         }
     }
+}
 
-    /// Gets a file by ID
-    pub fn get_file_by_id(&self, id: u32) -> FileResult<&File> {
-        match self.files.iter().find(|f| f.id == id) {
-            Some(file) => Ok(file),
-            None => Err(FileError::InvalidId(id)),
+impl FileDisplay for FileEntry<'_> {
+    fn display_summary(&self) -> String {
+        match self {
+            FileEntry::Text(f) => f.display_summary(),
+            FileEntry::Binary(f) => f.display_summary(),
         }
     }
 
-    /// Lists all files
-    pub fn list_files(&self) -> &[File] {
-        &self.files
+    fn display_detailed(&self) -> String {
+        match self {
+            FileEntry::Text(f) => f.display_detailed(),
+            FileEntry::Binary(f) => f.display_detailed(),
+        }
     }
+}
 
-    /// Deletes a file by name
-    pub fn delete_file(&mut self, name: &str) -> FileResult<()> {
-        match self.files.iter().position(|f| f.name == name) {
-            Some(index) => {
-                self.files.remove(index);
-                Ok(())
-            }
-            None => Err(FileError::NotFound(name.to_string())),
+/// A matching line found by `FileSystem::sliding_window_search`, with surrounding context
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub line_number: usize,
+    pub matched_line: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// Flesch Reading Ease result computed by `FileSystem::compute_readability_score`
+#[derive(Debug, Clone)]
+pub struct ReadabilityScore {
+    pub score: f64,
+    pub grade_level: String,
+    pub avg_words_per_sentence: f64,
+}
+
+impl ReadabilityScore {
+    /// Maps a Flesch Reading Ease score to its conventional school level
+    fn grade_for(score: f64) -> &'static str {
+        match score {
+            s if s >= 90.0 => "5th grade",
+            s if s >= 80.0 => "6th grade",
+            s if s >= 70.0 => "7th grade",
+            s if s >= 60.0 => "8th & 9th grade",
+            s if s >= 50.0 => "10th to 12th grade",
+            s if s >= 30.0 => "College",
+            s if s >= 10.0 => "College graduate",
+            _ => "Professional",
         }
     }
+}
 
-    /// Deletes a file by ID
-    pub fn delete_file_by_id(&mut self, id: u32) -> FileResult<()> {
-        match self.files.iter().position(|f| f.id == id) {
-            Some(index) => {
-                self.files.remove(index);
-                Ok(())
-            }
-            None => Err(FileError::InvalidId(id)),
+/// Maximum file name length before `lint_names` flags it
+pub const MAX_LINT_NAME_LEN: usize = 64;
+
+/// Potential problems with a file name reported by `FileSystem::lint_names`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintWarning {
+    TooLong,
+    ContainsSpaces,
+    ContainsSpecialChars,
+    UppercaseOnly,
+    DoubleExtension,
+    StartsWithDot,
+    EndsWithDot,
+}
+
+impl LintWarning {
+    /// Checks a single name and returns every warning that applies
+    pub fn check(name: &str) -> Vec<LintWarning> {
+        let mut warnings = Vec::new();
+
+        if name.chars().count() > MAX_LINT_NAME_LEN {
+            warnings.push(LintWarning::TooLong);
+        }
+        if name.contains(' ') {
+            warnings.push(LintWarning::ContainsSpaces);
+        }
+        if name.chars().any(|c| !c.is_alphanumeric() && !matches!(c, '.' | '_' | '-' | ' ')) {
+            warnings.push(LintWarning::ContainsSpecialChars);
+        }
+        if name.chars().any(|c| c.is_alphabetic()) && !name.chars().any(|c| c.is_lowercase()) {
+            warnings.push(LintWarning::UppercaseOnly);
+        }
+        if name.trim_matches('.').matches('.').count() > 1 {
+            warnings.push(LintWarning::DoubleExtension);
+        }
+        if name.starts_with('.') {
+            warnings.push(LintWarning::StartsWithDot);
+        }
+        if name.ends_with('.') {
+            warnings.push(LintWarning::EndsWithDot);
         }
+
+        warnings
     }
 
-    /// Gets the total number of files
-    pub fn file_count(&self) -> usize {
-        self.files.len()
+    /// Produces a name that resolves all warnings for the given name
+    pub fn fix(name: &str) -> String {
+        let mut fixed: String = name
+            .trim_matches('.')
+            .chars()
+            .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' })
+            .collect();
+
+        if fixed.chars().any(|c| c.is_alphabetic()) && !fixed.chars().any(|c| c.is_lowercase()) {
+            fixed = fixed.to_lowercase();
+        }
+
+        // Keep only the last dot so the real extension survives
+        if let Some(last_dot) = fixed.rfind('.') {
+            let (stem, ext) = fixed.split_at(last_dot);
+            fixed = format!("{}{}", stem.replace('.', "_"), ext);
+        }
+
+        if fixed.chars().count() > MAX_LINT_NAME_LEN {
+            let ext = fixed.rfind('.').map(|i| fixed[i..].to_string()).unwrap_or_default();
+            let stem_len = MAX_LINT_NAME_LEN.saturating_sub(ext.chars().count());
+            let stem: String = fixed.chars().take(stem_len).collect();
+            fixed = format!("{}{}", stem, ext);
+        }
+
+        fixed
     }
+}
 
-    /// Gets the total size of all files
-    pub fn total_size(&self) -> usize {
-        self.files.iter().map(|f| f.size).sum()
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            LintWarning::TooLong => "name is longer than 64 characters",
+            LintWarning::ContainsSpaces => "name contains spaces",
+            LintWarning::ContainsSpecialChars => "name contains special characters",
+            LintWarning::UppercaseOnly => "name is all uppercase",
+            LintWarning::DoubleExtension => "name has more than one extension",
+            LintWarning::StartsWithDot => "name starts with a dot",
+            LintWarning::EndsWithDot => "name ends with a dot",
+        };
+        write!(f, "{}", message)
     }
 }
 
-impl Default for FileSystem {
-    fn default() -> Self {
-        Self::new()
+/// Polls a file for content changes since the last check
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    name: String,
+    last_hash: u64,
+    last_content: Arc<String>,
+    last_modified: std::time::SystemTime,
+}
+
+impl FileWatcher {
+    /// Gets the name of the watched file
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks whether the content changed since the last check, and records the new state
+    pub fn has_changed(&mut self, fs: &FileSystem) -> FileResult<bool> {
+        Ok(self.diff(fs)?.is_some())
+    }
+
+    /// Returns the line diff since the last check, or `None` if nothing changed
+    pub fn diff(&mut self, fs: &FileSystem) -> FileResult<Option<Vec<DiffLine>>> {
+        let file = fs.resolve_symlink(&self.name)?;
+        if file.modified_at == self.last_modified {
+            return Ok(None);
+        }
+        self.last_modified = file.modified_at;
+
+        // A write may have stored identical content
+        let hash = content_hash(&file.content);
+        if hash == self.last_hash {
+            return Ok(None);
+        }
+
+        let lines = diff::diff_lines(&self.last_content, &file.content);
+        self.last_hash = hash;
+        self.last_content = Arc::clone(&file.content);
+        Ok(Some(lines))
+    }
+}
+
+/// Path of the root directory, which always exists
+pub const ROOT_DIR: &str = "/";
+
+/// A directory grouping files by ID. File names are unique within a directory,
+/// so `a.txt` can exist in both `/` and `/docs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directory {
+    /// Last component of the path, or "/" for the root
+    pub name: String,
+    pub files: Vec<u32>,
+    /// Names of child directories
+    pub subdirs: Vec<String>,
+}
+
+impl Directory {
+    fn new(name: &str) -> Self {
+        Directory {
+            name: name.to_string(),
+            files: Vec::new(),
+            subdirs: Vec::new(),
+        }
+    }
+}
+
+/// In-memory file system manager.
+///
+/// Methods that take a file name accept a path resolved against the current
+/// directory, such as `notes.txt`, `docs/notes.txt`, `../notes.txt` or `/docs/notes.txt`.
+pub struct FileSystem {
+    files: Vec<File>,
+    /// Maps each text file's absolute path to its position in `files`
+    name_index: HashMap<String, usize>,
+    /// Maps each text file's ID to its position in `files`
+    id_index: HashMap<u32, usize>,
+    /// Maps each extension to the IDs of the text files that have it
+    ext_index: HashMap<String, Vec<u32>>,
+    binary_files: Vec<BinaryFile>,
+    next_id: u32,
+    index_file: Option<String>,
+    /// Maps the ID of each file written by `search_and_collect` to the query it collected
+    collected: HashMap<u32, String>,
+    /// Upper bound on `total_size`, enforced when text files are created or written
+    max_total_size: Option<usize>,
+    /// Upper bound on the number of text and binary files
+    max_files: Option<usize>,
+    /// When set, every mutating operation is rejected
+    readonly: bool,
+    /// Directories keyed by absolute path
+    directories: HashMap<String, Directory>,
+    /// Maps every text and binary file's ID to the absolute path of its directory
+    file_dirs: HashMap<u32, String>,
+    /// Absolute path of the current directory
+    cwd: String,
+}
+
+impl FileSystem {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an empty filesystem with room for `capacity` text files
+    pub fn with_capacity(capacity: usize) -> Self {
+        FileSystem {
+            files: Vec::with_capacity(capacity),
+            name_index: HashMap::with_capacity(capacity),
+            id_index: HashMap::with_capacity(capacity),
+            ext_index: HashMap::new(),
+            binary_files: Vec::new(),
+            next_id: 1,
+            index_file: None,
+            collected: HashMap::new(),
+            max_total_size: None,
+            max_files: None,
+            readonly: false,
+            directories: HashMap::from([(ROOT_DIR.to_string(), Directory::new(ROOT_DIR))]),
+            file_dirs: HashMap::with_capacity(capacity),
+            cwd: ROOT_DIR.to_string(),
+        }
+    }
+
+    /// Creates an empty filesystem that holds at most `n` text and binary files
+    pub fn with_max_files(n: usize) -> Self {
+        FileSystem {
+            max_files: Some(n),
+            ..Self::new()
+        }
+    }
+
+    /// Freezes or unfreezes the whole filesystem
+    pub fn set_readonly(&mut self, readonly: bool) {
+        self.readonly = readonly;
+    }
+
+    /// Whether the filesystem is frozen
+    pub fn is_readonly(&self) -> bool {
+        self.readonly
+    }
+
+    /// Rejects mutations while the filesystem is read-only
+    fn check_not_readonly(&self) -> FileResult<()> {
+        if self.readonly {
+            return Err(FileError::AccessDenied("filesystem is read-only".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// How many more files can be created, or `None` if there is no limit
+    pub fn remaining_capacity(&self) -> Option<usize> {
+        self.max_files.map(|max| max.saturating_sub(self.file_count()))
+    }
+
+    /// Checks that one more file fits within `max_files`
+    fn check_quota(&self) -> FileResult<()> {
+        match self.max_files {
+            Some(max) if self.file_count() >= max => Err(FileError::QuotaExceeded(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates an empty filesystem whose files may hold at most `bytes` in total
+    pub fn with_max_total_size(bytes: usize) -> Self {
+        FileSystem {
+            max_total_size: Some(bytes),
+            ..Self::new()
+        }
+    }
+
+    /// Checks that replacing `old_size` bytes with `new_size` bytes stays within the size limit
+    fn check_size_limit(&self, old_size: usize, new_size: usize) -> FileResult<()> {
+        let Some(limit) = self.max_total_size else {
+            return Ok(());
+        };
+        let attempted = self.total_size() - old_size + new_size;
+        if attempted > limit {
+            return Err(FileError::SizeLimitExceeded { attempted, limit });
+        }
+        Ok(())
+    }
+
+    /// Creates a new file. `name` may include a directory, as in `docs/a.txt`,
+    /// which must already exist.
+    pub fn create_file(&mut self, name: String, content: String) -> FileResult<u32> {
+        self.check_not_readonly()?;
+
+        let (dir, name) = self.new_file_location(&name)?;
+        self.check_quota()?;
+        self.check_size_limit(0, content.len())?;
+
+        let id = self.next_id;
+        let file = File::new(id, name, content)?;
+        self.push_file(file, dir);
+        self.next_id += 1;
+        Ok(id)
+    }
+
+    /// Creates a new file owned by `owner`
+    pub fn create_file_owned(&mut self, name: String, content: String, owner: String) -> FileResult<u32> {
+        let id = self.create_file(name, content)?;
+        if let Some(file) = self.files.iter_mut().find(|f| f.id == id) {
+            file.owner = owner;
+        }
+        Ok(id)
+    }
+
+    /// Overwrites the file if it exists, otherwise creates it. Returns the file's ID either way.
+    pub fn upsert_file(&mut self, name: String, content: String) -> FileResult<u32> {
+        match self.find_file(&name) {
+            Ok(file) => {
+                let id = file.id;
+                self.write_file(&name, content)?;
+                Ok(id)
+            }
+            Err(_) => self.create_file(name, content),
+        }
+    }
+
+    /// Writes content to an existing file, following symlinks to their target
+    pub fn write_file(&mut self, name: &str, content: String) -> FileResult<()> {
+        let old_size = self.writable_target(name)?.size;
+        self.check_size_limit(old_size, content.len())?;
+        self.writable_target(name)?.write_content(content);
+        self.sync_index();
+        Ok(())
+    }
+
+    /// Appends content to the end of an existing file, following symlinks to their target
+    pub fn append_file(&mut self, name: &str, content: &str) -> FileResult<()> {
+        let old_size = self.writable_target(name)?.size;
+        self.check_size_limit(old_size, old_size + content.len())?;
+
+        let file = self.writable_target(name)?;
+        let mut appended = String::with_capacity(file.content.len() + content.len());
+        appended.push_str(&file.content);
+        appended.push_str(content);
+        file.write_content(appended);
+        self.sync_index();
+        Ok(())
+    }
+
+    /// Copies a file's content into a new file, returning the new ID
+    pub fn copy_file(&mut self, source: &str, dest: &str) -> FileResult<u32> {
+        if self.normalize_path(source) == self.normalize_path(dest) {
+            return Err(FileError::InvalidInput(format!("Cannot copy '{}' onto itself", source)));
+        }
+
+        let content = self.read_content(source)?.to_string();
+        self.create_file(dest.to_string(), content)
+    }
+
+    /// Creates `dest` holding the content of `name1`, a newline, then the content of `name2`.
+    /// Returns the new ID; the sources are left unchanged.
+    pub fn concat_files(&mut self, name1: &str, name2: &str, dest: String) -> FileResult<u32> {
+        self.concat_files_with(name1, name2, dest, "\n")
+    }
+
+    /// Like `concat_files`, but joins the two contents with `separator`
+    pub fn concat_files_with(&mut self, name1: &str, name2: &str, dest: String, separator: &str) -> FileResult<u32> {
+        let dest_path = self.normalize_path(&dest);
+        if dest_path == self.normalize_path(name1) || dest_path == self.normalize_path(name2) {
+            return Err(FileError::InvalidInput(format!("Cannot concatenate into source file '{}'", dest)));
+        }
+
+        let first = self.read_content(name1)?;
+        let second = self.read_content(name2)?;
+        let content = [first, second].join(separator);
+        self.create_file(dest, content)
+    }
+
+    /// Moves the lines after `at_line` into a new file `second_name`, leaving lines
+    /// 1..=`at_line` in `name` without their final newline, so `concat_files` undoes the split.
+    /// Returns the new file's ID.
+    pub fn split_file(&mut self, name: &str, at_line: usize, second_name: String) -> FileResult<u32> {
+        let existing = Arc::clone(&self.writable_target(name)?.content);
+        let lines: Vec<&str> = existing.split_inclusive('\n').collect();
+        if at_line == 0 || at_line >= lines.len() {
+            return Err(FileError::InvalidInput(format!(
+                "Cannot split '{}' at line {}: it must be between 1 and {}",
+                name,
+                at_line,
+                lines.len().saturating_sub(1)
+            )));
+        }
+        if self.name_taken(&second_name) {
+            return Err(FileError::AlreadyExists(second_name));
+        }
+
+        let first = lines[..at_line].concat();
+        let first = first.strip_suffix('\n').unwrap_or(&first).to_string();
+        let id = self.create_file(second_name, lines[at_line..].concat())?;
+        self.write_file(name, first)?;
+        Ok(id)
+    }
+
+    /// Renames a file, keeping its ID and metadata. A path in another directory moves
+    /// the file there, and naming an existing directory moves it in under its current name.
+    pub fn rename_file(&mut self, old_name: &str, new_name: &str) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        if new_name.trim().is_empty() {
+            return Err(FileError::InvalidInput("File name cannot be empty".to_string()));
+        }
+        let index = self.position(old_name)
+            .ok_or_else(|| FileError::NotFound(old_name.to_string()))?;
+        let old_path = self.path_at(index);
+
+        let mut new_path = self.normalize_path(new_name);
+        if self.directories.contains_key(&new_path) {
+            new_path = join_path(&new_path, &self.files[index].name);
+        }
+        if new_path == old_path {
+            return Ok(());
+        }
+
+        let (dir, name) = self.new_file_location(&new_path)?;
+        if let Some(target) = &self.files[index].symlink_target
+            && self.symlink_reaches(absolute_path(&dir, target), &new_path, Some(index))
+        {
+            return Err(FileError::InvalidInput(format!(
+                "Renaming '{}' to '{}' would create a symlink cycle",
+                old_name, new_name
+            )));
+        }
+        self.files[index].check_writable()?;
+        self.set_path(index, dir, name);
+        Ok(())
+    }
+
+    /// Empties a file's content while keeping its entry and ID
+    pub fn truncate_file(&mut self, name: &str) -> FileResult<()> {
+        let file = self.writable_target(name)?;
+        if !file.content.is_empty() {
+            file.write_content(String::new());
+            self.sync_index();
+        }
+        Ok(())
+    }
+
+    /// Replaces the first occurrence of `from` (or every occurrence if `all`) in a file,
+    /// following symlinks. Returns how many replacements were made.
+    pub fn replace_in_file(&mut self, name: &str, from: &str, to: &str, all: bool) -> FileResult<usize> {
+        if from.is_empty() {
+            return Err(FileError::InvalidInput("Search text cannot be empty".to_string()));
+        }
+
+        let content = Arc::clone(&self.writable_target(name)?.content);
+        let count = match content.matches(from).count() {
+            0 => return Ok(0),
+            count if all => count,
+            _ => 1,
+        };
+
+        let replaced = if all {
+            content.replace(from, to)
+        } else {
+            content.replacen(from, to, 1)
+        };
+        self.write_file(name, replaced)?;
+        Ok(count)
+    }
+
+    /// Replaces every occurrence of `from` in every writable file. Returns the number of
+    /// replacements per changed file's absolute path; symlinks are skipped so targets change once.
+    pub fn replace_all(&mut self, from: &str, to: &str) -> FileResult<HashMap<String, usize>> {
+        if from.is_empty() {
+            return Err(FileError::InvalidInput("Search text cannot be empty".to_string()));
+        }
+        self.check_not_readonly()?;
+
+        let paths: Vec<String> = (0..self.files.len())
+            .filter(|&index| !self.files[index].is_symlink && self.files[index].check_writable().is_ok())
+            .map(|index| self.path_at(index))
+            .collect();
+
+        let mut counts = HashMap::new();
+        for path in paths {
+            let count = self.replace_in_file(&path, from, to, true)?;
+            if count > 0 {
+                counts.insert(path, count);
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Inserts `content` plus a newline so it becomes line `line` (1-based) of a file.
+    /// Line 1 prepends and `line_count + 1` appends.
+    pub fn insert_at_line(&mut self, name: &str, line: usize, content: &str) -> FileResult<()> {
+        let existing = Arc::clone(&self.writable_target(name)?.content);
+        let line_count = existing.lines().count();
+        if line == 0 || line > line_count + 1 {
+            return Err(FileError::InvalidInput(format!(
+                "Line {} is out of range (1-{})",
+                line,
+                line_count + 1
+            )));
+        }
+
+        let mut lines: Vec<String> = existing.split_inclusive('\n').map(str::to_string).collect();
+        // Appending after a final line with no newline must not join the two lines
+        if line > lines.len()
+            && let Some(last) = lines.last_mut()
+            && !last.ends_with('\n')
+        {
+            last.push('\n');
+        }
+        lines.insert(line - 1, format!("{}\n", content));
+
+        self.write_file(name, lines.concat())
+    }
+
+    /// Deletes lines `start..=end` (1-based) of a file, clamping `end` to the last line.
+    /// Returns how many lines were removed.
+    pub fn delete_lines(&mut self, name: &str, start: usize, end: usize) -> FileResult<usize> {
+        if start == 0 || start > end {
+            return Err(FileError::InvalidInput(format!(
+                "Invalid line range {}-{}: lines start at 1 and start must not exceed end",
+                start, end
+            )));
+        }
+
+        let existing = Arc::clone(&self.writable_target(name)?.content);
+        let mut lines: Vec<&str> = existing.split_inclusive('\n').collect();
+        if start > lines.len() {
+            return Ok(0);
+        }
+
+        let end = end.min(lines.len());
+        lines.drain(start - 1..end);
+        self.write_file(name, lines.concat())?;
+        Ok(end - start + 1)
+    }
+
+    /// Replaces a file's permissions; this is allowed even if the file isn't writable
+    pub fn set_permissions(&mut self, name: &str, permissions: FilePermissions) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        match self.find_file_mut(name) {
+            Some(file) => {
+                file.permissions = permissions;
+                Ok(())
+            }
+            None => Err(FileError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Restores a previous version of a file (0 = most recent). The content being
+    /// replaced becomes a version itself, so a revert can be undone.
+    pub fn revert_to_version(&mut self, name: &str, version: usize) -> FileResult<()> {
+        let file = self.writable_target(name)?;
+        let content = match file.versions.get(version) {
+            Some((content, _)) => content.clone(),
+            None => {
+                return Err(FileError::InvalidInput(format!(
+                    "'{}' has no version {} ({} available)",
+                    file.name,
+                    version,
+                    file.versions.len()
+                )));
+            }
+        };
+        self.write_file(name, content)
+    }
+
+    /// Lists when each previous version of a file was written, most recent first
+    pub fn list_versions(&self, name: &str) -> FileResult<Vec<std::time::SystemTime>> {
+        Ok(self.resolve_symlink(name)?.versions.iter().map(|(_, time)| *time).collect())
+    }
+
+    /// Gets all files owned by `owner`
+    pub fn files_by_owner<'a>(&'a self, owner: &str) -> Vec<&'a File> {
+        self.files.iter().filter(|f| f.owner == owner).collect()
+    }
+
+    /// Gives a file to a new owner
+    pub fn transfer_ownership(&mut self, name: &str, new_owner: String) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        match self.find_file_mut(name) {
+            Some(file) => {
+                file.owner = new_owner;
+                Ok(())
+            }
+            None => Err(FileError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Deletes every file owned by `owner` and returns how many were removed. Like
+    /// `retain`, which does the work, it keeps locked and write-protected files and
+    /// fails on a read-only filesystem.
+    pub fn delete_by_owner(&mut self, owner: &str) -> FileResult<usize> {
+        self.retain(|f| f.owner != owner)
+    }
+
+    /// Locks a file so it can't be written, renamed, or deleted
+    pub fn lock_file(&mut self, name: &str) -> FileResult<()> {
+        self.set_locked(name, true)
+    }
+
+    /// Unlocks a previously locked file
+    pub fn unlock_file(&mut self, name: &str) -> FileResult<()> {
+        self.set_locked(name, false)
+    }
+
+    /// Locks a file by ID; locking an already-locked file is a no-op
+    pub fn lock_file_by_id(&mut self, id: u32) -> FileResult<()> {
+        self.set_locked_by_id(id, true)
+    }
+
+    /// Unlocks a previously locked file by ID
+    pub fn unlock_file_by_id(&mut self, id: u32) -> FileResult<()> {
+        self.set_locked_by_id(id, false)
+    }
+
+    fn set_locked_by_id(&mut self, id: u32, locked: bool) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        match self.files.iter_mut().find(|f| f.id == id) {
+            Some(file) => {
+                file.locked = locked;
+                Ok(())
+            }
+            None => Err(FileError::InvalidId(id)),
+        }
+    }
+
+    fn set_locked(&mut self, name: &str, locked: bool) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        match self.find_file_mut(name) {
+            Some(file) => {
+                file.locked = locked;
+                Ok(())
+            }
+            None => Err(FileError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Finds the file a write to `name` should land on, following symlinks and refusing locked files
+    fn writable_target(&mut self, name: &str) -> FileResult<&mut File> {
+        self.check_not_readonly()?;
+
+        let index = self.resolve_symlink_index(name)?;
+        let file = &mut self.files[index];
+        file.check_writable()?;
+        Ok(file)
+    }
+
+    /// Reads a file's content, following symlinks to their target, and bumps `accessed_at`
+    pub fn read_file(&mut self, name: &str) -> FileResult<&str> {
+        let index = self.resolve_symlink_index(name)?;
+        let file = &mut self.files[index];
+        file.check_read_permission()?;
+        file.accessed_at = std::time::SystemTime::now();
+        Ok(&file.content)
+    }
+
+    /// Iterates over a file's lines, following symlinks and bumping `accessed_at` like `read_file`
+    pub fn read_file_lines(&mut self, name: &str) -> FileResult<impl Iterator<Item = &str>> {
+        Ok(self.read_file(name)?.lines())
+    }
+
+    /// Reads lines `start..=end` (1-based) of a file, clamping `end` to the last line.
+    /// Follows symlinks and bumps `accessed_at` like `read_file`.
+    pub fn read_lines(&mut self, name: &str, start: usize, end: usize) -> FileResult<Vec<&str>> {
+        if start == 0 || start > end {
+            return Err(FileError::InvalidInput(format!(
+                "Invalid line range {}-{}: lines start at 1 and start must not exceed end",
+                start, end
+            )));
+        }
+        Ok(self.read_file_lines(name)?.skip(start - 1).take(end - start + 1).collect())
+    }
+
+    /// Reads a file's content without recording an access
+    fn read_content(&self, name: &str) -> FileResult<&str> {
+        Ok(&self.resolve_symlink(name)?.content)
+    }
+
+    /// Creates a symlink named `link_name` that points at `target_name`, which is resolved
+    /// relative to the link's directory. The target does not need to exist yet, but links
+    /// that would form a cycle are rejected.
+    pub fn create_symlink(&mut self, link_name: String, target_name: &str) -> FileResult<u32> {
+        self.check_not_readonly()?;
+
+        let (dir, name) = self.new_file_location(&link_name)?;
+        self.check_quota()?;
+        if self.symlink_reaches(absolute_path(&dir, target_name), &join_path(&dir, &name), None) {
+            return Err(FileError::InvalidInput(format!(
+                "Symlink '{}' -> '{}' would create a cycle",
+                link_name, target_name
+            )));
+        }
+
+        let id = self.next_id;
+        let file = File::new_symlink(id, name, target_name.to_string())?;
+        self.push_file(file, dir);
+        self.next_id += 1;
+        Ok(id)
+    }
+
+    /// Creates `new_name` sharing the same content object as `existing_name`.
+    /// Writing to either name afterwards gives it its own copy.
+    pub fn hardlink_file(&mut self, existing_name: &str, new_name: String) -> FileResult<u32> {
+        self.check_not_readonly()?;
+
+        let (dir, name) = self.new_file_location(&new_name)?;
+        let content = Arc::clone(&self.find_file(existing_name)?.content);
+        self.check_quota()?;
+        self.check_size_limit(0, content.len())?;
+        let id = self.next_id;
+        let mut file = File::new(id, name, String::new())?;
+        file.content = content;
+        file.size = file.content.len();
+        self.push_file(file, dir);
+        self.next_id += 1;
+        Ok(id)
+    }
+
+    /// Checks whether two names currently share the same content object
+    pub fn is_hardlinked(&self, a: &str, b: &str) -> bool {
+        match (self.find_file(a), self.find_file(b)) {
+            (Ok(a), Ok(b)) => Arc::ptr_eq(&a.content, &b.content),
+            _ => false,
+        }
+    }
+
+    /// Whether following symlinks from the path `start` reaches `link_path`, so a link
+    /// stored there would be circular. The file at position `moving`, if any, is treated
+    /// as gone, and a cycle the chain runs into elsewhere ends the walk.
+    fn symlink_reaches(&self, start: String, link_path: &str, moving: Option<usize>) -> bool {
+        let mut visited = HashSet::new();
+        let mut current = start;
+
+        loop {
+            if current == link_path {
+                return true;
+            }
+            if !visited.insert(current.clone()) {
+                return false;
+            }
+            match self.position(&current) {
+                Some(index) if Some(index) != moving => match &self.files[index].symlink_target {
+                    Some(next) => current = absolute_path(self.directory_of(self.files[index].id), next),
+                    None => return false,
+                },
+                _ => return false,
+            }
+        }
+    }
+
+    /// Follows a chain of symlinks until a regular file is reached
+    fn resolve_symlink(&self, name: &str) -> FileResult<&File> {
+        Ok(&self.files[self.resolve_symlink_index(name)?])
+    }
+
+    /// Like `resolve_symlink`, but returns the target's position in `files`
+    fn resolve_symlink_index(&self, name: &str) -> FileResult<usize> {
+        let mut index = self.position(name)
+            .ok_or_else(|| FileError::NotFound(name.to_string()))?;
+        let mut hops = 0;
+
+        while let Some(target) = &self.files[index].symlink_target {
+            hops += 1;
+            if hops > self.files.len() {
+                return Err(FileError::InvalidInput(format!("Symlink '{}' is circular", name)));
+            }
+            let path = absolute_path(self.directory_of(self.files[index].id), target);
+            index = self.position(&path)
+                .ok_or_else(|| FileError::NotFound(target.clone()))?;
+        }
+
+        Ok(index)
+    }
+
+    /// Gets a file by name and bumps its `accessed_at`
+    pub fn get_file(&mut self, name: &str) -> FileResult<&File> {
+        Ok(self.touch(name)?)
+    }
+
+    /// Gets a file by name without recording an access
+    fn find_file(&self, name: &str) -> FileResult<&File> {
+        match self.position(name) {
+            Some(index) => Ok(&self.files[index]),
+            None => Err(FileError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Position of a text file in `files`, via the name index
+    fn position(&self, name: &str) -> Option<usize> {
+        self.name_index.get(&self.normalize_path(name)).copied()
+    }
+
+    /// Absolute path of the text file at `index`
+    fn path_at(&self, index: usize) -> String {
+        let file = &self.files[index];
+        join_path(self.directory_of(file.id), &file.name)
+    }
+
+    /// Looks up a text file by name for mutation
+    fn find_file_mut(&mut self, name: &str) -> Option<&mut File> {
+        self.position(name).map(|index| &mut self.files[index])
+    }
+
+    /// Stores a new text file in directory `dir` and records it in the indexes
+    fn push_file(&mut self, file: File, dir: String) {
+        self.name_index.insert(join_path(&dir, &file.name), self.files.len());
+        self.id_index.insert(file.id, self.files.len());
+        if let Some(ext) = file.extension() {
+            self.ext_index.entry(ext.to_string()).or_default().push(file.id);
+        }
+        self.place(file.id, dir);
+        self.files.push(file);
+        self.sync_index();
+    }
+
+    /// Removes the text file at `index`, shifting later files' index entries down by one
+    fn remove_file_at(&mut self, index: usize) -> File {
+        self.name_index.remove(&self.path_at(index));
+        let file = self.files.remove(index);
+        self.id_index.remove(&file.id);
+        unindex_extension(&mut self.ext_index, &file);
+        for later in &self.files[index..] {
+            let path = join_path(self.file_dirs.get(&later.id).map_or(ROOT_DIR, String::as_str), &later.name);
+            if let Some(position) = self.name_index.get_mut(&path) {
+                *position -= 1;
+            }
+            if let Some(position) = self.id_index.get_mut(&later.id) {
+                *position -= 1;
+            }
+        }
+        self.unplace(file.id);
+        self.sync_index();
+        file
+    }
+
+    /// Stores a new binary file in directory `dir`
+    fn push_binary_file(&mut self, file: BinaryFile, dir: String) {
+        self.place(file.id, dir);
+        self.binary_files.push(file);
+    }
+
+    /// Removes the binary file at `index`
+    fn remove_binary_file_at(&mut self, index: usize) -> BinaryFile {
+        let file = self.binary_files.remove(index);
+        self.unplace(file.id);
+        file
+    }
+
+    /// Lists a file's ID in directory `dir`
+    fn place(&mut self, id: u32, dir: String) {
+        if let Some(directory) = self.directories.get_mut(&dir) {
+            directory.files.push(id);
+        }
+        self.file_dirs.insert(id, dir);
+    }
+
+    /// Drops a file's ID from the directory listing it
+    fn unplace(&mut self, id: u32) {
+        if let Some(dir) = self.file_dirs.remove(&id)
+            && let Some(directory) = self.directories.get_mut(&dir)
+        {
+            directory.files.retain(|&other| other != id);
+        }
+    }
+
+    /// Renames the text file at `index` within its directory, updating the name and
+    /// extension indexes. Returns the old name.
+    fn set_name(&mut self, index: usize, new_name: String) -> String {
+        let dir = self.directory_of(self.files[index].id).to_string();
+        self.set_path(index, dir, new_name)
+    }
+
+    /// Moves the text file at `index` to `dir` under `new_name`, updating the name and
+    /// extension indexes and the directory listings. Returns the old name.
+    fn set_path(&mut self, index: usize, dir: String, new_name: String) -> String {
+        let old_path = self.path_at(index);
+        self.name_index.remove(&old_path);
+        unindex_extension(&mut self.ext_index, &self.files[index]);
+        let old_name = std::mem::replace(&mut self.files[index].name, new_name);
+
+        let id = self.files[index].id;
+        if self.directory_of(id) != dir {
+            self.unplace(id);
+            self.place(id, dir);
+        }
+        self.name_index.insert(self.path_at(index), index);
+
+        let file = &self.files[index];
+        if let Some(ext) = file.extension() {
+            self.ext_index.entry(ext.to_string()).or_default().push(file.id);
+        }
+        if self.index_file.as_deref() == Some(old_path.as_str()) {
+            self.index_file = Some(self.path_at(index));
+        }
+        self.sync_index();
+        old_name
+    }
+
+    /// Recomputes every index after `files` has been reordered, shrunk, renamed or renumbered,
+    /// and drops files that no longer exist from their directories
+    fn rebuild_indexes(&mut self) {
+        let live: HashSet<u32> = self.files
+            .iter()
+            .map(|f| f.id)
+            .chain(self.binary_files.iter().map(|f| f.id))
+            .collect();
+        self.file_dirs.retain(|id, _| live.contains(id));
+        for directory in self.directories.values_mut() {
+            directory.files.retain(|id| live.contains(id));
+        }
+
+        self.name_index.clear();
+        self.id_index.clear();
+        self.ext_index.clear();
+        for (index, file) in self.files.iter().enumerate() {
+            let dir = self.file_dirs.get(&file.id).map_or(ROOT_DIR, String::as_str);
+            self.name_index.insert(join_path(dir, &file.name), index);
+            self.id_index.insert(file.id, index);
+            if let Some(ext) = file.extension() {
+                self.ext_index.entry(ext.to_string()).or_default().push(file.id);
+            }
+        }
+        self.sync_index();
+    }
+
+    /// Checks that the name, ID and extension indexes and the directory listings agree
+    /// with the stored files and hold nothing else. Intended for debugging and assertions.
+    pub fn verify_index_integrity(&self) -> bool {
+        let positions_match = self.files
+            .iter()
+            .enumerate()
+            .all(|(index, file)| {
+                self.name_index.get(&self.path_at(index)) == Some(&index) && self.id_index.get(&file.id) == Some(&index)
+            });
+        let directories_match = self.entries().iter().all(|entry| {
+            self.file_dirs
+                .get(&entry.id())
+                .and_then(|dir| self.directories.get(dir))
+                .is_some_and(|dir| dir.files.iter().filter(|&&id| id == entry.id()).count() == 1)
+        });
+        let listed: usize = self.directories.values().map(|dir| dir.files.len()).sum();
+        let extensions_match = self.ext_index.iter().all(|(ext, ids)| {
+            !ids.is_empty()
+                && ids.iter().all(|id| {
+                    self.id_index.get(id).is_some_and(|&index| self.files[index].extension() == Some(ext.as_str()))
+                })
+        });
+        let indexed_extensions: usize = self.ext_index.values().map(Vec::len).sum();
+
+        positions_match
+            && extensions_match
+            && directories_match
+            && listed == self.file_count()
+            && self.file_dirs.len() == self.file_count()
+            && self.name_index.len() == self.files.len()
+            && self.id_index.len() == self.files.len()
+            && indexed_extensions == self.files.iter().filter(|f| f.extension().is_some()).count()
+    }
+
+    /// Marks a file as accessed now
+    fn touch(&mut self, name: &str) -> FileResult<&mut File> {
+        match self.find_file_mut(name) {
+            Some(file) => {
+                file.accessed_at = std::time::SystemTime::now();
+                Ok(file)
+            }
+            None => Err(FileError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Gets a file by ID
+    pub fn get_file_by_id(&self, id: u32) -> FileResult<&File> {
+        match self.id_index.get(&id) {
+            Some(&index) => Ok(&self.files[index]),
+            None => Err(FileError::InvalidId(id)),
+        }
+    }
+
+    /// Gets a file for direct mutation. Nothing is validated: callers that change
+    /// `content` must call `File::sync_size` and then `refresh_index` afterwards, and
+    /// names must be changed with `rename_file` so the indexes stay correct.
+    pub fn get_file_mut(&mut self, name: &str) -> FileResult<&mut File> {
+        self.check_not_readonly()?;
+
+        self.find_file_mut(name)
+            .ok_or_else(|| FileError::NotFound(name.to_string()))
+    }
+
+    /// Gets a file by ID for direct mutation; see `get_file_mut`
+    pub fn get_file_by_id_mut(&mut self, id: u32) -> FileResult<&mut File> {
+        self.check_not_readonly()?;
+
+        match self.id_index.get(&id) {
+            Some(&index) => Ok(&mut self.files[index]),
+            None => Err(FileError::InvalidId(id)),
+        }
+    }
+
+    /// Lists all text files
+    pub fn list_files(&self) -> &[File] {
+        &self.files
+    }
+
+    /// Iterates over the text files
+    pub fn iter(&self) -> std::slice::Iter<'_, File> {
+        self.files.iter()
+    }
+
+    /// Iterates mutably over the text files. Use `rename_file` rather than
+    /// assigning `name` here, or the indexes go stale. Unlike `get_file_mut`, this
+    /// ignores the read-only flag so `&mut FileSystem` can implement `IntoIterator`;
+    /// check `is_readonly` first where that matters.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, File> {
+        self.files.iter_mut()
+    }
+
+    /// Consumes the filesystem and returns its text files; binary files are dropped
+    pub fn into_files(self) -> Vec<File> {
+        self.files
+    }
+
+    /// Lists text files ordered by size, then name
+    pub fn list_files_sorted(&self, order: SortOrder) -> Vec<&File> {
+        let mut files: Vec<&File> = self.files.iter().collect();
+        files.sort();
+        if order == SortOrder::Desc {
+            files.reverse();
+        }
+        files
+    }
+
+    /// Sorts the stored files in place. This is allowed on a read-only filesystem,
+    /// since no file changes.
+    pub fn sort_files(&mut self, by: SortField, order: SortOrder) {
+        self.files.sort_by(|a, b| by.compare(a, b));
+        if order == SortOrder::Desc {
+            self.files.reverse();
+        }
+        self.rebuild_indexes();
+    }
+
+    /// Returns the files in sorted order without reordering storage
+    pub fn sorted_files(&self, by: SortField, order: SortOrder) -> Vec<&File> {
+        let mut files: Vec<&File> = self.files.iter().collect();
+        files.sort_by(|a, b| by.compare(a, b));
+        if order == SortOrder::Desc {
+            files.reverse();
+        }
+        files
+    }
+
+    /// Lists text and binary files together, ordered by ID
+    pub fn entries(&self) -> Vec<FileEntry<'_>> {
+        let mut entries: Vec<FileEntry> = self.files
+            .iter()
+            .map(FileEntry::Text)
+            .chain(self.binary_files.iter().map(FileEntry::Binary))
+            .collect();
+        entries.sort_by_key(FileEntry::id);
+        entries
+    }
+
+    /// Gets the absolute path of the current directory
+    pub fn pwd(&self) -> &str {
+        &self.cwd
+    }
+
+    /// Changes the current directory
+    pub fn cd(&mut self, path: &str) -> FileResult<()> {
+        let path = self.normalize_path(path);
+        if !self.directories.contains_key(&path) {
+            return Err(FileError::NotFound(path));
+        }
+        self.cwd = path;
+        Ok(())
+    }
+
+    /// Creates a directory; its parent must already exist
+    pub fn mkdir(&mut self, path: &str) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        let path = self.normalize_path(path);
+        if self.name_taken(&path) {
+            return Err(FileError::AlreadyExists(path));
+        }
+
+        let (parent, name) = split_path(&path);
+        let parent_dir = self.directories
+            .get_mut(parent)
+            .ok_or_else(|| FileError::NotFound(parent.to_string()))?;
+        parent_dir.subdirs.push(name.to_string());
+        self.directories.insert(path.clone(), Directory::new(name));
+        Ok(())
+    }
+
+    /// Removes a directory. Without `recursive` it must be empty; with it, the
+    /// files and subdirectories inside are deleted too unless any file is locked.
+    pub fn rmdir(&mut self, path: &str, recursive: bool) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        let path = self.normalize_path(path);
+        if path == ROOT_DIR {
+            return Err(FileError::InvalidInput("Cannot remove the root directory".to_string()));
+        }
+        if !self.directories.contains_key(&path) {
+            return Err(FileError::NotFound(path));
+        }
+        if is_within(&self.cwd, &path) {
+            return Err(FileError::InvalidInput(format!("Cannot remove '{}' while it is the current directory", path)));
+        }
+
+        let doomed_dirs: Vec<String> = self.directories.keys().filter(|dir| is_within(dir, &path)).cloned().collect();
+        let doomed_files: Vec<u32> = self.entries()
+            .iter()
+            .map(FileEntry::id)
+            .filter(|&id| doomed_dirs.iter().any(|dir| self.directories[dir].files.contains(&id)))
+            .collect();
+
+        if !recursive && (doomed_dirs.len() > 1 || !doomed_files.is_empty()) {
+            return Err(FileError::InvalidInput(format!("Directory '{}' is not empty", path)));
+        }
+        if let Some(Err(e)) = self.files
+            .iter()
+            .filter(|f| doomed_files.contains(&f.id))
+            .map(File::check_writable)
+            .find(Result::is_err)
+        {
+            return Err(e);
+        }
+
+        for id in doomed_files {
+            self.delete_file_by_id(id)?;
+        }
+        for dir in doomed_dirs {
+            self.directories.remove(&dir);
+        }
+        let (parent, name) = split_path(&path);
+        if let Some(parent_dir) = self.directories.get_mut(parent) {
+            parent_dir.subdirs.retain(|subdir| subdir != name);
+        }
+        Ok(())
+    }
+
+    /// Gets the directory at `path`, relative to the current directory
+    pub fn directory(&self, path: &str) -> FileResult<&Directory> {
+        let path = self.normalize_path(path);
+        self.directories.get(&path).ok_or(FileError::NotFound(path))
+    }
+
+    /// Gets the absolute path of the directory holding the file with this ID
+    pub fn directory_of(&self, id: u32) -> &str {
+        self.file_dirs.get(&id).map_or(ROOT_DIR, String::as_str)
+    }
+
+    /// Absolute path of the text or binary file with the given ID
+    pub fn path_of(&self, id: u32) -> FileResult<String> {
+        let name = match self.id_index.get(&id) {
+            Some(&index) => &self.files[index].name,
+            None => &self.binary_files.iter().find(|f| f.id == id).ok_or(FileError::InvalidId(id))?.name,
+        };
+        Ok(join_path(self.directory_of(id), name))
+    }
+
+    /// Lists the current directory's subdirectories and files, ordered by name and ID
+    pub fn list_cwd(&self) -> (Vec<&str>, Vec<FileEntry<'_>>) {
+        let mut subdirs: Vec<&str> = self.directories[&self.cwd].subdirs.iter().map(String::as_str).collect();
+        subdirs.sort_unstable();
+
+        let entries = self.entries()
+            .into_iter()
+            .filter(|entry| self.directory_of(entry.id()) == self.cwd)
+            .collect();
+        (subdirs, entries)
+    }
+
+    /// Resolves a file path such as `notes.txt`, `docs/notes.txt` or `/docs/notes.txt`
+    /// against the current directory, returning the file's absolute path
+    pub fn resolve_file_path(&self, path: &str) -> FileResult<String> {
+        if self.position(path).is_none() && self.binary_position(path).is_none() {
+            return Err(FileError::NotFound(path.to_string()));
+        }
+        Ok(self.normalize_path(path))
+    }
+
+    /// Turns a path relative to the current directory into a normalized absolute path
+    fn normalize_path(&self, path: &str) -> String {
+        absolute_path(&self.cwd, path)
+    }
+
+    /// Splits the path of a file about to be created into its directory and name,
+    /// checking that the directory exists and nothing is stored at the path yet
+    fn new_file_location(&self, path: &str) -> FileResult<(String, String)> {
+        let absolute = self.normalize_path(path);
+        let (dir, name) = split_path(&absolute);
+        if name.is_empty() || path.ends_with('/') {
+            return Err(FileError::InvalidInput("File name cannot be empty".to_string()));
+        }
+        if !self.directories.contains_key(dir) {
+            return Err(FileError::NotFound(dir.to_string()));
+        }
+        if self.name_taken(&absolute) {
+            return Err(FileError::AlreadyExists(path.to_string()));
+        }
+        Ok((dir.to_string(), name.to_string()))
+    }
+
+    /// Position of a binary file in `binary_files`
+    fn binary_position(&self, name: &str) -> Option<usize> {
+        let path = self.normalize_path(name);
+        self.binary_files
+            .iter()
+            .position(|f| join_path(self.directory_of(f.id), &f.name) == path)
+    }
+
+    /// Creates a new binary file; `name` may include a directory, as with `create_file`
+    pub fn create_binary_file(&mut self, name: String, data: Vec<u8>) -> FileResult<u32> {
+        self.check_not_readonly()?;
+
+        let (dir, name) = self.new_file_location(&name)?;
+        self.check_quota()?;
+        self.check_size_limit(0, data.len())?;
+        let id = self.next_id;
+        let file = BinaryFile::new(id, name, data)?;
+        self.push_binary_file(file, dir);
+        self.next_id += 1;
+        Ok(id)
+    }
+
+    /// Reads a binary file's data
+    pub fn read_binary_file(&self, name: &str) -> FileResult<&[u8]> {
+        match self.binary_position(name) {
+            Some(index) => Ok(&self.binary_files[index].content),
+            None => Err(FileError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Replaces a binary file's data
+    pub fn write_binary_file(&mut self, name: &str, data: Vec<u8>) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        match self.binary_position(name) {
+            Some(index) => {
+                self.check_size_limit(self.binary_files[index].size, data.len())?;
+                self.binary_files[index].write_content(data);
+                Ok(())
+            }
+            None => Err(FileError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Deletes a text or binary file by name
+    pub fn delete_file(&mut self, name: &str) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        if let Some(index) = self.binary_position(name) {
+            self.remove_binary_file_at(index);
+            return Ok(());
+        }
+
+        match self.position(name) {
+            Some(index) => {
+                self.files[index].check_writable()?;
+                self.remove_file_at(index);
+                Ok(())
+            }
+            None => Err(FileError::NotFound(name.to_string())),
+        }
+    }
+
+    /// Deletes each named file, carrying on past failures, which are reported together
+    pub fn batch_delete(&mut self, names: &[&str]) -> FileResult<()> {
+        let errors = names
+            .iter()
+            .filter_map(|name| self.delete_file(name).err())
+            .collect();
+        batch_result(errors)
+    }
+
+    /// Deletes a text or binary file by ID
+    pub fn delete_file_by_id(&mut self, id: u32) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        if let Some(index) = self.binary_files.iter().position(|f| f.id == id) {
+            self.remove_binary_file_at(index);
+            return Ok(());
+        }
+
+        match self.id_index.get(&id).copied() {
+            Some(index) => {
+                self.files[index].check_writable()?;
+                self.remove_file_at(index);
+                Ok(())
+            }
+            None => Err(FileError::InvalidId(id)),
+        }
+    }
+
+    /// Gets the total number of text and binary files
+    pub fn file_count(&self) -> usize {
+        self.files.len() + self.binary_files.len()
+    }
+
+    /// Gets the total size of all text and binary files
+    pub fn total_size(&self) -> usize {
+        self.files.iter().map(|f| f.size).sum::<usize>() + self.binary_files.iter().map(|f| f.size).sum::<usize>()
+    }
+
+    /// Checks whether a text file, binary file or directory already uses the path `name`
+    fn name_taken(&self, name: &str) -> bool {
+        let path = self.normalize_path(name);
+        self.name_index.contains_key(&path)
+            || self.directories.contains_key(&path)
+            || self.binary_position(&path).is_some()
+    }
+
+    /// Counts files with no content; symlinks don't count
+    pub fn count_empty_files(&self) -> usize {
+        self.files.iter().filter(|f| f.is_empty() && !f.is_symlink).count()
+    }
+
+    /// Deletes every empty file and returns how many were removed. Symlinks are kept,
+    /// and so are locked and write-protected files, as in `retain`.
+    pub fn delete_empty_files(&mut self) -> FileResult<usize> {
+        self.check_not_readonly()?;
+
+        let before = self.files.len();
+        self.files.retain(|f| !f.is_empty() || f.is_symlink || f.check_writable().is_err());
+        self.rebuild_indexes();
+        Ok(before - self.files.len())
+    }
+
+    /// Keeps only the text files matching `predicate` and returns how many were removed.
+    /// Locked and write-protected files are always kept, here and in the other bulk
+    /// removals. The count is wrapped in a `FileResult` because a read-only filesystem
+    /// refuses with `AccessDenied` instead of removing anything.
+    pub fn retain(&mut self, predicate: impl Fn(&File) -> bool) -> FileResult<usize> {
+        self.check_not_readonly()?;
+
+        let before = self.files.len();
+        self.files.retain(|f| f.check_writable().is_err() || predicate(f));
+        self.rebuild_indexes();
+        Ok(before - self.files.len())
+    }
+
+    /// Removes every binary file and every text file but the locked and write-protected
+    /// ones, which are kept as in `retain`, and returns how many were removed. Once
+    /// nothing is left, IDs restart at 1 and only the root directory remains. Fails
+    /// on a read-only filesystem.
+    pub fn clear(&mut self) -> FileResult<usize> {
+        self.check_not_readonly()?;
+
+        let binary_count = self.binary_files.len();
+        self.binary_files.clear();
+        let removed = self.retain(|_| false)? + binary_count;
+
+        if self.files.is_empty() {
+            self.next_id = 1;
+            self.index_file = None;
+            self.collected.clear();
+            self.directories = HashMap::from([(ROOT_DIR.to_string(), Directory::new(ROOT_DIR))]);
+            self.file_dirs.clear();
+            self.cwd = ROOT_DIR.to_string();
+        }
+        Ok(removed)
+    }
+
+    /// Renumbers text and binary files from 1 in ID order, closing gaps left by deletions.
+    /// Returns a map from old IDs to new IDs.
+    pub fn compact(&mut self) -> FileResult<HashMap<u32, u32>> {
+        self.check_not_readonly()?;
+
+        let mut old_ids: Vec<u32> = self.files
+            .iter()
+            .map(|f| f.id)
+            .chain(self.binary_files.iter().map(|f| f.id))
+            .collect();
+        old_ids.sort_unstable();
+
+        let mapping: HashMap<u32, u32> = old_ids
+            .into_iter()
+            .zip(1..)
+            .collect();
+
+        for file in &mut self.files {
+            file.id = mapping[&file.id];
+        }
+        for file in &mut self.binary_files {
+            file.id = mapping[&file.id];
+        }
+        for directory in self.directories.values_mut() {
+            directory.files = directory.files.iter().filter_map(|id| mapping.get(id).copied()).collect();
+        }
+        self.file_dirs = self.file_dirs
+            .drain()
+            .filter_map(|(id, dir)| Some((*mapping.get(&id)?, dir)))
+            .collect();
+        self.collected = self.collected
+            .drain()
+            .filter_map(|(id, query)| Some((*mapping.get(&id)?, query)))
+            .collect();
+        self.rebuild_indexes();
+        self.next_id = mapping.len() as u32 + 1;
+        Ok(mapping)
+    }
+
+    /// Splits the text files into those matching `predicate` and the rest, each in a
+    /// fresh filesystem with IDs renumbered from 1. Both keep the directory tree, and
+    /// every file stays in its directory. Binary files, which `predicate` can't inspect,
+    /// all go to the rest, numbered after its text files.
+    pub fn partition(self, predicate: impl Fn(&File) -> bool) -> (FileSystem, FileSystem) {
+        let tree: HashMap<String, Directory> = self.directories
+            .iter()
+            .map(|(path, dir)| (path.clone(), Directory { files: Vec::new(), ..dir.clone() }))
+            .collect();
+        let mut matching = FileSystem { directories: tree.clone(), ..FileSystem::new() };
+        let mut rest = FileSystem { directories: tree, ..FileSystem::new() };
+
+        for mut file in self.files {
+            let dir = self.file_dirs.get(&file.id).cloned().unwrap_or_else(|| ROOT_DIR.to_string());
+            let target = if predicate(&file) { &mut matching } else { &mut rest };
+            file.id = target.next_id;
+            target.push_file(file, dir);
+            target.next_id += 1;
+        }
+        for mut file in self.binary_files {
+            let dir = self.file_dirs.get(&file.id).cloned().unwrap_or_else(|| ROOT_DIR.to_string());
+            file.id = rest.next_id;
+            rest.push_binary_file(file, dir);
+            rest.next_id += 1;
+        }
+        (matching, rest)
+    }
+
+    /// Gets the text files in `self` whose paths don't appear in `other`
+    pub fn difference<'a>(&'a self, other: &FileSystem) -> Vec<&'a File> {
+        (0..self.files.len())
+            .filter(|&index| !other.name_index.contains_key(&self.path_at(index)))
+            .map(|index| &self.files[index])
+            .collect()
+    }
+
+    /// Gets the text files in `self` whose paths also appear in `other`; content is not compared
+    pub fn intersection<'a>(&'a self, other: &FileSystem) -> Vec<&'a File> {
+        (0..self.files.len())
+            .filter(|&index| other.name_index.contains_key(&self.path_at(index)))
+            .map(|index| &self.files[index])
+            .collect()
+    }
+
+    /// Gets the text files whose paths appear in exactly one of `self` and `other`,
+    /// those from `self` first
+    pub fn symmetric_difference<'a>(&'a self, other: &'a FileSystem) -> Vec<&'a File> {
+        let mut files = self.difference(other);
+        files.extend(other.difference(self));
+        files
+    }
+
+    /// Copies every file from `other` into this filesystem under fresh IDs. Paths are
+    /// taken relative to the current directory, and missing directories are created.
+    /// Name collisions are skipped and reported together as a `BatchError`
+    /// of `AlreadyExists` errors once the rest have been merged.
+    pub fn merge(&mut self, other: FileSystem) -> FileResult<()> {
+        self.merge_files(other, false)
+    }
+
+    /// Like `merge`, but overwrites the content of files whose names collide
+    pub fn merge_overwrite(&mut self, other: FileSystem) -> FileResult<()> {
+        self.merge_files(other, true)
+    }
+
+    fn merge_files(&mut self, other: FileSystem, overwrite: bool) -> FileResult<()> {
+        let mut errors = Vec::new();
+
+        let mut dirs: Vec<&String> = other.directories.keys().filter(|dir| *dir != ROOT_DIR).collect();
+        dirs.sort();
+        for dir in dirs {
+            let relative = &dir[1..];
+            if !self.directories.contains_key(&self.normalize_path(relative)) {
+                self.mkdir(relative)?;
+            }
+        }
+
+        let relative_path = |id: u32, name: &str| join_path(other.directory_of(id), name)[1..].to_string();
+        let files: Vec<(String, String)> = other.files
+            .iter()
+            .map(|f| (relative_path(f.id, &f.name), f.content.to_string()))
+            .collect();
+        let binary_files: Vec<(String, Vec<u8>)> = other.binary_files
+            .iter()
+            .map(|f| (relative_path(f.id, &f.name), f.content.clone()))
+            .collect();
+
+        for (path, content) in files {
+            let result = match self.create_file(path.clone(), content.clone()) {
+                Err(FileError::AlreadyExists(_)) if overwrite => self.write_file(&path, content),
+                result => result.map(|_| ()),
+            };
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+
+        for (path, content) in binary_files {
+            let result = match self.create_binary_file(path.clone(), content.clone()) {
+                Err(FileError::AlreadyExists(_)) if overwrite => self.write_binary_file(&path, content),
+                result => result.map(|_| ()),
+            };
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+
+        batch_result(errors)
+    }
+
+    /// Produces a unified diff from `old_name` to `new_name`, following symlinks
+    pub fn diff(&self, old_name: &str, new_name: &str) -> FileResult<String> {
+        let old = self.read_content(old_name)?;
+        let new = self.read_content(new_name)?;
+        Ok(diff::unified_diff(old_name, new_name, old, new, DIFF_CONTEXT_LINES))
+    }
+
+    /// Groups the IDs of files with identical checksums, returning only groups of two or more
+    pub fn find_duplicates(&self) -> Vec<Vec<u32>> {
+        let mut groups: Vec<(String, Vec<u32>)> = Vec::new();
+
+        for file in self.files.iter().filter(|f| !f.is_symlink) {
+            let checksum = file.checksum();
+            match groups.iter_mut().find(|(c, _)| *c == checksum) {
+                Some((_, ids)) => ids.push(file.id),
+                None => groups.push((checksum, vec![file.id])),
+            }
+        }
+
+        groups
+            .into_iter()
+            .map(|(_, ids)| ids)
+            .filter(|ids| ids.len() >= 2)
+            .collect()
+    }
+
+    /// Gets the total number of lines across all files
+    pub fn total_line_count(&self) -> usize {
+        self.files.iter().map(File::line_count).sum()
+    }
+
+    /// Gets the total number of words across all files
+    pub fn total_word_count(&self) -> usize {
+        self.files.iter().map(File::word_count).sum()
+    }
+
+    /// Computes the full statistics report, extending `get_stats` with per-file details;
+    /// like it, the report covers the text files only
+    pub fn stats_report(&self) -> StatsReport {
+        let summary = self.get_stats();
+
+        let largest = self.largest_file();
+        let smallest = self.smallest_file();
+        let last_modified = self.files.iter().rev().max_by_key(|f| f.modified_at);
+
+        let entropy_avg = if summary.file_count == 0 {
+            0.0
+        } else {
+            self.files.iter().map(|f| stats::shannon_entropy(f.content.as_bytes())).sum::<f64>() / summary.file_count as f64
+        };
+
+        StatsReport {
+            summary,
+            largest: largest.map(|f| (f.name.clone(), f.size)),
+            smallest: smallest.map(|f| (f.name.clone(), f.size)),
+            last_modified: last_modified.map(|f| f.name.clone()),
+            newest: self.newest_file().map(|f| f.name.clone()),
+            oldest: self.oldest_file().map(|f| f.name.clone()),
+            empty_files: self.count_empty_files(),
+            entropy_avg,
+        }
+    }
+
+    /// Computes the core statistics: counts, sizes and file types. Every field covers
+    /// the text files only, as line, word and entropy figures don't apply to binary files.
+    pub fn get_stats(&self) -> FileStats {
+        let file_count = self.files.len();
+        let total_size: usize = self.files.iter().map(|f| f.size).sum();
+        let average_size_bytes = match file_count {
+            0 => 0.0,
+            count => total_size as f64 / count as f64,
+        };
+
+        let mut extension_counts = HashMap::new();
+        for file in &self.files {
+            let ext = file.extension().unwrap_or("no extension").to_string();
+            *extension_counts.entry(ext).or_insert(0) += 1;
+        }
+
+        let largest_file_id = self.largest_file().map(|f| f.id);
+
+        FileStats {
+            file_count,
+            total_size,
+            average_size_bytes,
+            total_lines: self.total_line_count(),
+            total_words: self.total_word_count(),
+            largest_file_id,
+            extension_counts,
+        }
+    }
+
+    /// Gets the mean file size in bytes, or 0.0 if there are no files
+    pub fn average_size(&self) -> f64 {
+        match self.file_count() {
+            0 => 0.0,
+            count => self.total_size() as f64 / count as f64,
+        }
+    }
+
+    /// Gets the largest text file; ties go to the lowest ID
+    pub fn largest_file(&self) -> Option<&File> {
+        self.files.iter().max_by(|a, b| a.size.cmp(&b.size).then(b.id.cmp(&a.id)))
+    }
+
+    /// Gets the smallest text file; ties go to the lowest ID
+    pub fn smallest_file(&self) -> Option<&File> {
+        self.files.iter().min_by(|a, b| a.size.cmp(&b.size).then(a.id.cmp(&b.id)))
+    }
+
+    /// Gets up to `n` text files from largest to smallest; ties go to the lowest ID
+    pub fn top_n_largest(&self, n: usize) -> Vec<&File> {
+        self.top_n_by(n, |a, b| b.size.cmp(&a.size).then(a.id.cmp(&b.id)))
+    }
+
+    /// Gets up to `n` text files from smallest to largest; ties go to the lowest ID
+    pub fn top_n_smallest(&self, n: usize) -> Vec<&File> {
+        self.top_n_by(n, |a, b| a.size.cmp(&b.size).then(a.id.cmp(&b.id)))
+    }
+
+    /// Selects the first `n` files under `compare` without sorting the rest
+    fn top_n_by(&self, n: usize, compare: impl Fn(&&File, &&File) -> std::cmp::Ordering) -> Vec<&File> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let mut files: Vec<&File> = self.files.iter().collect();
+        if n < files.len() {
+            files.select_nth_unstable_by(n - 1, &compare);
+            files.truncate(n);
+        }
+        files.sort_by(compare);
+        files
+    }
+
+    /// Gets the most recently created text file; ties go to the highest ID
+    pub fn newest_file(&self) -> Option<&File> {
+        self.files.iter().max_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)))
+    }
+
+    /// Gets the earliest created text file; ties go to the lowest ID
+    pub fn oldest_file(&self) -> Option<&File> {
+        self.files.iter().min_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)))
+    }
+
+    /// Adds a tag to a file; tags are trimmed and lowercased, and duplicates are ignored
+    pub fn add_tag(&mut self, name: &str, tag: &str) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        let tag = normalize_tag(tag)?;
+        let file = self.find_file_mut(name)
+            .ok_or_else(|| FileError::NotFound(name.to_string()))?;
+
+        if !file.tags.contains(&tag) {
+            file.tags.push(tag);
+        }
+        Ok(())
+    }
+
+    /// Removes a tag from a file; removing a tag that isn't present is a no-op
+    pub fn remove_tag(&mut self, name: &str, tag: &str) -> FileResult<()> {
+        self.check_not_readonly()?;
+
+        let tag = normalize_tag(tag)?;
+        let file = self.find_file_mut(name)
+            .ok_or_else(|| FileError::NotFound(name.to_string()))?;
+
+        file.tags.retain(|t| *t != tag);
+        Ok(())
+    }
+
+    /// Gets all files carrying the given tag
+    pub fn files_with_tag<'a>(&'a self, tag: &str) -> Vec<&'a File> {
+        let tag = tag.trim().to_lowercase();
+        self.files.iter().filter(|f| f.tags.contains(&tag)).collect()
+    }
+
+    /// Lists every tag in use, sorted and deduplicated
+    pub fn list_all_tags(&self) -> Vec<String> {
+        let tags: BTreeSet<&String> = self.files.iter().flat_map(|f| &f.tags).collect();
+        tags.into_iter().cloned().collect()
+    }
+
+    /// Adds heuristic tags to a file, returning only the tags that were newly added
+    pub fn auto_tag(&mut self, name: &str) -> FileResult<Vec<String>> {
+        self.check_not_readonly()?;
+
+        let file = self.find_file_mut(name)
+            .ok_or_else(|| FileError::NotFound(name.to_string()))?;
+
+        let mut added = Vec::new();
+        for tag in file.suggested_tags() {
+            if !file.tags.iter().any(|t| t == tag) {
+                file.tags.push(tag.to_string());
+                added.push(tag.to_string());
+            }
+        }
+        Ok(added)
+    }
+
+    /// Auto-tags every file, returning the newly added tags per file path
+    pub fn auto_tag_all(&mut self) -> FileResult<Vec<(String, Vec<String>)>> {
+        self.check_not_readonly()?;
+
+        let paths: Vec<String> = (0..self.files.len()).map(|index| self.path_at(index)).collect();
+        Ok(paths
+            .into_iter()
+            .filter_map(|path| match self.auto_tag(&path) {
+                Ok(added) if !added.is_empty() => Some((path, added)),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Finds all other files whose content mentions `name` (case-sensitive). The file
+    /// at path `name`, if any, is left out.
+    pub fn cross_reference(&self, name: &str) -> Vec<&File> {
+        let own = self.position(name);
+        self.files
+            .iter()
+            .enumerate()
+            .filter(|&(index, f)| Some(index) != own && f.content.contains(name))
+            .map(|(_, f)| f)
+            .collect()
+    }
+
+    /// Maps every file name to the files that mention it
+    pub fn cross_reference_all(&self) -> HashMap<String, Vec<&File>> {
+        self.files
+            .iter()
+            .map(|f| (f.name.clone(), self.cross_reference(&f.name)))
+            .collect()
+    }
+
+    /// Builds an "average" of several files by majority vote at each character position.
+    /// Shorter files are padded with spaces; ties go to the file listed first.
+    pub fn compute_average_content(&self, names: &[&str]) -> FileResult<String> {
+        if names.is_empty() {
+            return Err(FileError::InvalidInput("At least one file name is required".to_string()));
+        }
+
+        let contents = names
+            .iter()
+            .map(|name| Ok(self.find_file(name)?.content.chars().collect::<Vec<char>>()))
+            .collect::<FileResult<Vec<_>>>()?;
+        let length = contents.iter().map(Vec::len).max().unwrap_or(0);
+
+        let mut average = String::with_capacity(length);
+        for i in 0..length {
+            let column: Vec<char> = contents.iter().map(|c| c.get(i).copied().unwrap_or(' ')).collect();
+            let mut winner = (' ', 0);
+            for &c in &column {
+                let votes = column.iter().filter(|&&other| other == c).count();
+                if votes > winner.1 {
+                    winner = (c, votes);
+                }
+            }
+            average.push(winner.0);
+        }
+
+        Ok(average.trim_end_matches(' ').to_string())
+    }
+
+    /// Finds files whose content contains `query` (case-sensitive), in ID order.
+    /// An empty query matches every file.
+    pub fn find_by_content(&self, query: &str) -> Vec<&File> {
+        let mut files: Vec<&File> = self.files.iter().filter(|f| f.content.contains(query)).collect();
+        files.sort_by_key(|f| f.id);
+        files
+    }
+
+    /// Like `find_by_content`, but ignores case
+    pub fn find_by_content_ignore_case(&self, query: &str) -> Vec<&File> {
+        let query = query.to_lowercase();
+        let mut files: Vec<&File> = self.files
+            .iter()
+            .filter(|f| f.content.to_lowercase().contains(&query))
+            .collect();
+        files.sort_by_key(|f| f.id);
+        files
+    }
+
+    /// Gets all files with the given extension (a leading dot is ignored)
+    pub fn filter_by_extension(&self, ext: &str) -> Vec<&File> {
+        let ext = ext.trim_start_matches('.');
+        let mut positions: Vec<usize> = self.ext_index
+            .get(ext)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.id_index.get(id).copied())
+            .collect();
+        positions.sort_unstable();
+        positions.into_iter().map(|index| &self.files[index]).collect()
+    }
+
+    /// Gets all files whose name has no extension
+    pub fn filter_by_no_extension(&self) -> Vec<&File> {
+        self.files.iter().filter(|f| f.extension().is_none()).collect()
+    }
+
+    /// Gets all files with `min <= size <= max`
+    pub fn filter_by_size_range(&self, min: usize, max: usize) -> FileResult<Vec<&File>> {
+        if min > max {
+            return Err(FileError::InvalidInput(format!(
+                "Minimum size {} is greater than maximum size {}",
+                min, max
+            )));
+        }
+        Ok(self.files.iter().filter(|f| (min..=max).contains(&f.size)).collect())
+    }
+
+    /// Gets all files strictly larger than `threshold` bytes
+    pub fn files_larger_than(&self, threshold: usize) -> Vec<&File> {
+        self.files.iter().filter(|f| f.size > threshold).collect()
+    }
+
+    /// Gets all files strictly smaller than `threshold` bytes
+    pub fn files_smaller_than(&self, threshold: usize) -> Vec<&File> {
+        self.files.iter().filter(|f| f.size < threshold).collect()
+    }
+
+    /// Gets all files created at or after `time`
+    pub fn files_created_after(&self, time: std::time::SystemTime) -> Vec<&File> {
+        self.files.iter().filter(|f| f.created_at >= time).collect()
+    }
+
+    /// Gets all files created at or before `time`
+    pub fn files_created_before(&self, time: std::time::SystemTime) -> Vec<&File> {
+        self.files.iter().filter(|f| f.created_at <= time).collect()
+    }
+
+    /// Gets all files with `start <= created_at <= end`
+    pub fn files_created_between(&self, start: std::time::SystemTime, end: std::time::SystemTime) -> FileResult<Vec<&File>> {
+        if start > end {
+            return Err(FileError::InvalidInput("Start time is after end time".to_string()));
+        }
+        Ok(self.files.iter().filter(|f| (start..=end).contains(&f.created_at)).collect())
+    }
+
+    /// Finds files in the directory `name` points into whose name equals its last
+    /// component ignoring case. `create_file` is case-sensitive, so several files can match.
+    pub fn find_case_insensitive(&self, name: &str) -> Vec<&File> {
+        let path = self.normalize_path(name);
+        let (dir, name) = split_path(&path);
+        let name = name.to_lowercase();
+        self.files
+            .iter()
+            .filter(|f| self.directory_of(f.id) == dir && f.name.to_lowercase() == name)
+            .collect()
+    }
+
+    /// Finds files whose name matches a glob pattern, in ID order.
+    /// `*` matches any run of characters and `?` matches exactly one.
+    pub fn find_by_name_pattern(&self, pattern: &str) -> Vec<&File> {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let mut files: Vec<&File> = self.files
+            .iter()
+            .filter(|f| {
+                let name: Vec<char> = f.name.chars().collect();
+                glob_match(&pattern, &name)
+            })
+            .collect();
+        files.sort_by_key(|f| f.id);
+        files
+    }
+
+    /// Finds every line in every file that contains `query`, as `(file, line_number, line)`.
+    /// Files that `search_and_collect` wrote for the same query are skipped, so its
+    /// results don't match themselves.
+    pub fn search_content(&self, query: &str, case_sensitive: bool) -> Vec<(&File, usize, &str)> {
+        let query_lower = query.to_lowercase();
+        let mut matches = Vec::new();
+
+        for file in &self.files {
+            if self.collected.get(&file.id).is_some_and(|collected| collected == query) {
+                continue;
+            }
+            for (index, line) in file.content.lines().enumerate() {
+                let found = if case_sensitive {
+                    line.contains(query)
+                } else {
+                    line.to_lowercase().contains(&query_lower)
+                };
+                if found {
+                    matches.push((file, index + 1, line));
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// Finds the lines of one file that contain `query`, as `(line_number, line)`
+    pub fn search_with_line_numbers(&self, name: &str, query: &str) -> FileResult<Vec<(usize, &str)>> {
+        Ok(self.read_content(name)?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.contains(query))
+            .map(|(index, line)| (index + 1, line))
+            .collect())
+    }
+
+    /// Finds every line in every file that contains `query`, as `(file_name, line_number, line)`
+    pub fn grep_all(&self, query: &str) -> Vec<(String, usize, &str)> {
+        self.search_content(query, true)
+            .into_iter()
+            .map(|(file, line_number, line)| (file.name.clone(), line_number, line))
+            .collect()
+    }
+
+    /// Collects every match of `query` into a new file, one `<file>:<line>: <text>` entry per line.
+    /// Later searches for the same query leave the new file out.
+    pub fn search_and_collect(&mut self, query: &str, output_name: String, case_sensitive: bool) -> FileResult<u32> {
+        if self.name_taken(&output_name) {
+            return Err(FileError::AlreadyExists(output_name));
+        }
+
+        let collected = self
+            .search_content(query, case_sensitive)
+            .iter()
+            .map(|(file, line_number, line)| format!("{}:{}: {}", file.name, line_number, line))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let id = self.create_file(output_name, collected)?;
+        self.collected.insert(id, query.to_string());
+        Ok(id)
+    }
+
+    /// Starts watching a file for content changes
+    pub fn watch_file(&self, name: &str) -> FileResult<FileWatcher> {
+        let file = self.resolve_symlink(name)?;
+        Ok(FileWatcher {
+            name: self.normalize_path(name),
+            last_hash: content_hash(&file.content),
+            last_content: Arc::clone(&file.content),
+            last_modified: file.modified_at,
+        })
+    }
+
+    /// Creates a table-of-contents file listing every other file, which is refreshed
+    /// whenever a file is created, renamed, written or deleted.
+    pub fn create_index_file(&mut self, index_name: String) -> FileResult<u32> {
+        let index_path = self.normalize_path(&index_name);
+        let content = self.index_content(&index_path);
+        let id = self.create_file(index_name, content)?;
+        self.index_file = Some(index_path);
+        Ok(id)
+    }
+
+    /// Regenerates the index file, if one exists, to reflect the current files. Changes
+    /// made through `FileSystem` methods do this already; it's needed after editing
+    /// files through `get_file_mut`, or to learn why an automatic refresh left the index
+    /// alone. Fails with `SizeLimitExceeded` if the new index would not fit, leaving the
+    /// old one. On a read-only filesystem this is a no-op rather than an error, because
+    /// nothing can have changed.
+    pub fn refresh_index(&mut self) -> FileResult<()> {
+        if self.readonly {
+            return Ok(());
+        }
+
+        let Some(index_path) = self.index_file.clone() else {
+            return Ok(());
+        };
+
+        let content = self.index_content(&index_path);
+        let Some(index) = self.position(&index_path) else {
+            self.index_file = None;
+            return Ok(());
+        };
+        let file = &self.files[index];
+        if *file.content != content && file.check_writable().is_ok() {
+            self.check_size_limit(file.size, content.len())?;
+            self.files[index].write_content(content);
+        }
+        Ok(())
+    }
+
+    /// Refreshes the index file after a change. A new index that doesn't fit the size
+    /// limit is skipped here; `refresh_index` reports the error.
+    fn sync_index(&mut self) {
+        let _ = self.refresh_index();
+    }
+
+    /// Formats one `<id>. <name> (<size> bytes) - <extension>` line per file other than
+    /// the index at `index_path`, sorted by name
+    fn index_content(&self, index_path: &str) -> String {
+        let mut files: Vec<&File> = (0..self.files.len())
+            .filter(|&index| self.path_at(index) != index_path)
+            .map(|index| &self.files[index])
+            .collect();
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+
+        files
+            .iter()
+            .map(|f| format!("{}. {} ({} bytes) - {}", f.id, f.name, f.size, f.extension().unwrap_or("none")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Reports how IDs have been allocated and where deletions left gaps
+    pub fn generate_id_report(&self) -> IdReport {
+        let ids: HashSet<u32> = self.entries().iter().map(FileEntry::id).collect();
+        let issued = self.next_id.saturating_sub(1);
+        let gaps: Vec<u32> = (1..self.next_id).filter(|id| !ids.contains(id)).collect();
+
+        IdReport {
+            next_id: self.next_id,
+            allocated: ids.len(),
+            gaps,
+            max_id: ids.iter().copied().max().unwrap_or(0),
+            utilization: if issued == 0 { 1.0 } else { ids.len() as f64 / issued as f64 },
+        }
+    }
+
+    /// Sorts the rows of a CSV file by the given column, keeping the header row at the top
+    pub fn csv_sort(&mut self, name: &str, column: usize, numeric: bool, descending: bool) -> FileResult<()> {
+        let content = self.read_content(name)?.to_string();
+        let mut rows: Vec<Vec<String>> = content.lines().map(parse_csv_line).collect();
+
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let header = rows.remove(0);
+        if column >= header.len() {
+            return Err(FileError::InvalidInput(format!(
+                "Column {} is out of range ({} columns)",
+                column,
+                header.len()
+            )));
+        }
+        if rows.iter().any(|row| column >= row.len()) {
+            return Err(FileError::InvalidInput(format!("Column {} is missing in some rows", column)));
+        }
+
+        if numeric {
+            let mut keyed = Vec::with_capacity(rows.len());
+            for row in rows {
+                let key = row[column].trim().parse::<f64>().map_err(|_| {
+                    FileError::InvalidInput(format!("'{}' is not a number", row[column]))
+                })?;
+                keyed.push((key, row));
+            }
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            rows = keyed.into_iter().map(|(_, row)| row).collect();
+        } else {
+            rows.sort_by(|a, b| a[column].cmp(&b[column]));
+        }
+
+        if descending {
+            rows.reverse();
+        }
+
+        let mut sorted = format_csv_line(&header);
+        for row in &rows {
+            sorted.push('\n');
+            sorted.push_str(&format_csv_line(row));
+        }
+        if content.ends_with('\n') {
+            sorted.push('\n');
+        }
+
+        self.write_file(name, sorted)
+    }
+
+    /// Finds the longest leading substring shared by the given file names
+    pub fn find_longest_common_prefix(&self, names: &[&str]) -> FileResult<String> {
+        self.ensure_files_exist(names)?;
+        let mut prefix: Vec<char> = names[0].chars().collect();
+
+        for name in &names[1..] {
+            let shared = prefix.iter().zip(name.chars()).take_while(|(a, b)| **a == *b).count();
+            prefix.truncate(shared);
+        }
+
+        Ok(prefix.into_iter().collect())
+    }
+
+    /// Finds the longest trailing substring shared by the given file names
+    pub fn find_longest_common_suffix(&self, names: &[&str]) -> FileResult<String> {
+        self.ensure_files_exist(names)?;
+        let mut suffix: Vec<char> = names[0].chars().rev().collect();
+
+        for name in &names[1..] {
+            let shared = suffix.iter().zip(name.chars().rev()).take_while(|(a, b)| **a == *b).count();
+            suffix.truncate(shared);
+        }
+
+        Ok(suffix.into_iter().rev().collect())
+    }
+
+    /// Validates that every name refers to an existing file
+    fn ensure_files_exist(&self, names: &[&str]) -> FileResult<()> {
+        if names.is_empty() {
+            return Err(FileError::InvalidInput("At least one file name is required".to_string()));
+        }
+        if let Some(missing) = names.iter().find(|name| self.position(name).is_none()) {
+            return Err(FileError::InvalidInput(format!("File '{}' does not exist", missing)));
+        }
+        Ok(())
+    }
+
+    /// Renames every file whose name matches `from_pattern` (a regex), substituting
+    /// `to_pattern` which may contain back-references such as `$1`.
+    /// Returns the `(old, new)` name pairs; nothing is renamed if any new name collides.
+    pub fn batch_rename(&mut self, from_pattern: &str, to_pattern: &str) -> FileResult<Vec<(String, String)>> {
+        self.check_not_readonly()?;
+
+        let regex = Regex::new(from_pattern)
+            .map_err(|e| FileError::InvalidInput(format!("Invalid pattern '{}': {}", from_pattern, e)))?;
+
+        let renames: Vec<(usize, String)> = self.files
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| regex.is_match(&f.name))
+            .map(|(index, f)| (index, regex.replace(&f.name, to_pattern).into_owned()))
+            .filter(|(index, new_name)| *new_name != self.files[*index].name)
+            .collect();
+
+        for (index, _) in &renames {
+            self.files[*index].check_writable()?;
+        }
+
+        let renamed_indices: HashSet<usize> = renames.iter().map(|(index, _)| *index).collect();
+        let mut taken: HashSet<String> = (0..self.files.len())
+            .filter(|index| !renamed_indices.contains(index))
+            .map(|index| self.path_at(index))
+            .chain(self.binary_files.iter().map(|f| join_path(self.directory_of(f.id), &f.name)))
+            .chain(self.directories.keys().cloned())
+            .collect();
+
+        for (index, new_name) in &renames {
+            if new_name.trim().is_empty() {
+                return Err(FileError::InvalidInput("File name cannot be empty".to_string()));
+            }
+            if new_name.contains('/') {
+                return Err(FileError::InvalidInput(format!("File name '{}' cannot contain '/'", new_name)));
+            }
+            if !taken.insert(join_path(self.directory_of(self.files[*index].id), new_name)) {
+                return Err(FileError::AlreadyExists(new_name.clone()));
+            }
+        }
+
+        let mut pairs = Vec::with_capacity(renames.len());
+        for (index, new_name) in renames {
+            let old_name = std::mem::replace(&mut self.files[index].name, new_name.clone());
+            pairs.push((old_name, new_name));
+        }
+        self.rebuild_indexes();
+        Ok(pairs)
+    }
+
+    /// Pairs files from two name lists by position
+    pub fn zip_files<'a>(&'a self, names_a: &[&str], names_b: &[&str]) -> FileResult<Vec<(&'a File, &'a File)>> {
+        if names_a.len() != names_b.len() {
+            return Err(FileError::InvalidInput(format!(
+                "Cannot pair {} files with {} files",
+                names_a.len(),
+                names_b.len()
+            )));
+        }
+
+        names_a
+            .iter()
+            .zip(names_b)
+            .map(|(a, b)| Ok((self.find_file(a)?, self.find_file(b)?)))
+            .collect()
+    }
+
+    /// Finds every line containing `pattern`, including `context_lines` lines
+    /// before and after each match (like `grep -C N`). Line numbers are 1-based.
+    pub fn sliding_window_search(&self, name: &str, pattern: &str, context_lines: usize) -> FileResult<Vec<SearchResult>> {
+        let lines: Vec<&str> = self.read_content(name)?.lines().collect();
+
+        Ok(lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.contains(pattern))
+            .map(|(index, line)| {
+                let start = index.saturating_sub(context_lines);
+                let end = (index + 1 + context_lines).min(lines.len());
+                SearchResult {
+                    line_number: index + 1,
+                    matched_line: line.to_string(),
+                    before: lines[start..index].iter().map(|l| l.to_string()).collect(),
+                    after: lines[index + 1..end].iter().map(|l| l.to_string()).collect(),
+                }
+            })
+            .collect())
+    }
+
+    /// Computes the Flesch Reading Ease score of a file's content
+    pub fn compute_readability_score(&self, name: &str) -> FileResult<ReadabilityScore> {
+        let content = self.read_content(name)?;
+
+        let words: Vec<&str> = content
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+            .filter(|w| !w.is_empty())
+            .collect();
+        if words.is_empty() {
+            return Err(FileError::InvalidInput(format!("File '{}' contains no words", name)));
+        }
+
+        let sentences = content
+            .split(['.', '!', '?'])
+            .filter(|s| s.chars().any(|c| c.is_alphanumeric()))
+            .count()
+            .max(1);
+        let syllables: usize = words.iter().map(|w| count_syllables(w)).sum();
+
+        let word_count = words.len() as f64;
+        let avg_words_per_sentence = word_count / sentences as f64;
+        let score = 206.835 - 1.015 * avg_words_per_sentence - 84.6 * (syllables as f64 / word_count);
+
+        Ok(ReadabilityScore {
+            score,
+            grade_level: ReadabilityScore::grade_for(score).to_string(),
+            avg_words_per_sentence,
+        })
+    }
+
+    /// Appends `pad_char` until the content is at least `target_bytes` long.
+    /// Multi-byte characters may overshoot the target by a few bytes.
+    pub fn pad_to_size(&mut self, name: &str, target_bytes: usize, pad_char: char) -> FileResult<()> {
+        let file = self.writable_target(name)?;
+
+        if target_bytes < file.size {
+            return Err(FileError::InvalidInput(format!(
+                "Target size {} is smaller than current size {}; use truncate instead",
+                target_bytes, file.size
+            )));
+        }
+
+        let missing = target_bytes - file.size;
+        let count = missing.div_ceil(pad_char.len_utf8());
+        if count == 0 {
+            return Ok(());
+        }
+
+        let mut content = file.content.to_string();
+        content.extend(std::iter::repeat_n(pad_char, count));
+        self.write_file(name, content)
+    }
+
+    /// Builds a pipeline that applies the given transforms in order
+    pub fn content_pipeline(transforms: &[TransformStep]) -> ContentPipeline {
+        ContentPipeline::new(transforms)
+    }
+
+    /// Runs a pipeline over a file's content and stores the result
+    pub fn apply_pipeline(&mut self, name: &str, pipeline: &ContentPipeline) -> FileResult<()> {
+        let transformed = pipeline.apply(self.read_content(name)?);
+        self.write_file(name, transformed)
+    }
+
+    /// Serializes the whole filesystem, including directories, binary files and every
+    /// file's metadata, into a compact binary snapshot; see `snapshot::encode`.
+    /// Limits, the read-only flag and the index file are not included.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut directories: Vec<String> = self.directories.keys().filter(|dir| *dir != ROOT_DIR).cloned().collect();
+        directories.sort();
+
+        let files = self.files
+            .iter()
+            .map(|f| SnapshotRecord {
+                id: f.id,
+                directory: self.directory_of(f.id).to_string(),
+                name: f.name.clone(),
+                content: f.content.to_string(),
+                created_at: f.created_at,
+                modified_at: f.modified_at,
+                accessed_at: f.accessed_at,
+                symlink_target: f.symlink_target.clone(),
+                tags: f.tags.clone(),
+                locked: f.locked,
+                permissions: f.permissions,
+                owner: f.owner.clone(),
+                versions: f.versions.clone(),
+                max_versions: f.max_versions,
+            })
+            .collect();
+        let binary_files = self.binary_files
+            .iter()
+            .map(|f| BinaryRecord {
+                id: f.id,
+                directory: self.directory_of(f.id).to_string(),
+                name: f.name.clone(),
+                content: f.content.clone(),
+                created_at: f.created_at,
+                modified_at: f.modified_at,
+            })
+            .collect();
+
+        snapshot::encode(&Snapshot {
+            next_id: self.next_id,
+            cwd: self.cwd.clone(),
+            directories,
+            files,
+            binary_files,
+        })
+    }
+
+    /// Rebuilds a filesystem from bytes produced by `snapshot`
+    pub fn restore(bytes: &[u8]) -> FileResult<FileSystem> {
+        let mut snapshot = snapshot::decode(bytes)?;
+        let mut fs = FileSystem::with_capacity(snapshot.files.len());
+
+        snapshot.directories.sort();
+        for directory in &snapshot.directories {
+            fs.mkdir(directory)?;
+        }
+
+        for record in snapshot.files {
+            let (dir, name) = fs.restored_location(record.id, &record.directory, &record.name)?;
+            let mut file = File::new(record.id, name, record.content)?;
+            file.created_at = record.created_at;
+            file.modified_at = record.modified_at;
+            file.accessed_at = record.accessed_at;
+            file.is_symlink = record.symlink_target.is_some();
+            file.symlink_target = record.symlink_target;
+            file.tags = record.tags;
+            file.locked = record.locked;
+            file.permissions = record.permissions;
+            file.owner = record.owner;
+            file.versions = record.versions;
+            file.max_versions = record.max_versions;
+            fs.next_id = fs.next_id.max(record.id.saturating_add(1));
+            fs.push_file(file, dir);
+        }
+
+        for record in snapshot.binary_files {
+            let (dir, name) = fs.restored_location(record.id, &record.directory, &record.name)?;
+            let mut file = BinaryFile::new(record.id, name, record.content)?;
+            file.created_at = record.created_at;
+            file.modified_at = record.modified_at;
+            fs.next_id = fs.next_id.max(record.id.saturating_add(1));
+            fs.push_binary_file(file, dir);
+        }
+
+        fs.next_id = fs.next_id.max(snapshot.next_id);
+        fs.cd(&snapshot.cwd)?;
+        Ok(fs)
+    }
+
+    /// Validates the ID and location of a file being restored from saved state,
+    /// returning its directory and name
+    fn restored_location(&self, id: u32, directory: &str, name: &str) -> FileResult<(String, String)> {
+        if id == 0 || self.id_index.contains_key(&id) || self.file_dirs.contains_key(&id) {
+            return Err(FileError::InvalidId(id));
+        }
+        if !directory.starts_with('/') || name.contains('/') {
+            return Err(FileError::InvalidInput(format!("Invalid file location '{}' in '{}'", name, directory)));
+        }
+        self.new_file_location(&join_path(directory, name))
+    }
+
+    /// Exports the text files as `{"version":2,"files":[...],"directories":[...]}`, the
+    /// files being `File::to_json` objects with their `directory` added and the directories
+    /// every one but the root, so empty ones survive too. Binary files aren't exported,
+    /// as JSON strings only hold text; `snapshot` keeps them.
+    pub fn export_to_json(&self) -> String {
+        let files: Vec<String> = self.files
+            .iter()
+            .map(|f| format!("{{{},\"directory\":\"{}\"}}", f.json_fields(), stats::escape_json(self.directory_of(f.id))))
+            .collect();
+        let mut directories: Vec<&String> = self.directories.keys().filter(|dir| *dir != ROOT_DIR).collect();
+        directories.sort();
+        let directories: Vec<String> = directories.iter().map(|dir| format!("\"{}\"", stats::escape_json(dir))).collect();
+        format!(
+            "{{\"version\":{},\"files\":[{}],\"directories\":[{}]}}",
+            SchemaVersion::CURRENT.number(),
+            files.join(","),
+            directories.join(",")
+        )
+    }
+
+    /// Builds a filesystem from JSON produced by `export_to_json`; V1 documents are
+    /// migrated first (see `migrate_v1_file`).
+    /// `name` and `content` are required; a missing `id` gets the next free ID,
+    /// a missing `directory` is the root, a missing `created_at` defaults to now and a
+    /// missing `modified_at` to `created_at`. `tags` and a non-null `symlink_target` are
+    /// restored when present, and the `directories` are created before any file.
+    pub fn import_from_json(text: &str) -> FileResult<FileSystem> {
+        let document = json::parse(text)?;
+        let (version, items) = match &document {
+            JsonValue::Array(items) => (SchemaVersion::V1, items.as_slice()),
+            JsonValue::Object(_) => {
+                let version = document.get("version")
+                    .and_then(JsonValue::as_u64)
+                    .ok_or_else(|| FileError::InvalidInput("Saved state is missing its schema version".to_string()))?;
+                let items = document.get("files")
+                    .and_then(JsonValue::as_array)
+                    .ok_or_else(|| FileError::InvalidInput("Saved state is missing its files array".to_string()))?;
+                (SchemaVersion::from_number(version)?, items)
+            }
+            _ => return Err(FileError::InvalidInput("Expected a JSON array or saved state object".to_string())),
+        };
+        let items: Vec<JsonValue> = match version {
+            SchemaVersion::V1 => items.iter().map(migrate_v1_file).collect(),
+            SchemaVersion::V2 => items.to_vec(),
+        };
+        let mut fs = FileSystem::with_capacity(items.len());
+
+        let invalid_directories = || FileError::InvalidInput("Directories must be an array of absolute paths".to_string());
+        let mut directories = Vec::new();
+        if let Some(value) = document.get("directories") {
+            for directory in value.as_array().ok_or_else(invalid_directories)? {
+                match directory.as_str() {
+                    Some(directory) if directory.starts_with('/') => directories.push(directory),
+                    _ => return Err(invalid_directories()),
+                }
+            }
+        }
+        directories.sort();
+        for directory in directories {
+            fs.mkdir(directory)?;
+        }
+
+        for item in items {
+            let field = |key: &str| {
+                item.get(key)
+                    .and_then(JsonValue::as_str)
+                    .ok_or_else(|| FileError::InvalidInput(format!("File entry is missing string field '{}'", key)))
+            };
+            let name = field("name")?.to_string();
+            let content = field("content")?.to_string();
+            let directory = match item.get("directory") {
+                Some(_) => field("directory")?,
+                None => ROOT_DIR,
+            };
+
+            let id = match item.get("id") {
+                Some(value) => value.as_u64()
+                    .and_then(|id| u32::try_from(id).ok())
+                    .filter(|&id| id != 0)
+                    .ok_or_else(|| FileError::InvalidInput("File ID must be a positive integer".to_string()))?,
+                None => fs.next_id,
+            };
+            let (dir, name) = fs.restored_location(id, directory, &name)?;
+
+            let mut file = File::new(id, name, content)?;
+            if let Some(secs) = item.get("created_at").and_then(JsonValue::as_u64) {
+                let created_at = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+                file.created_at = created_at;
+                file.modified_at = created_at;
+                file.accessed_at = created_at;
+            }
+            if let Some(secs) = item.get("modified_at").and_then(JsonValue::as_u64) {
+                file.modified_at = std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+            }
+            if let Some(tags) = item.get("tags") {
+                let tags = tags.as_array()
+                    .ok_or_else(|| FileError::InvalidInput("File tags must be an array of strings".to_string()))?;
+                for tag in tags {
+                    let tag = tag.as_str()
+                        .ok_or_else(|| FileError::InvalidInput("File tags must be an array of strings".to_string()))?;
+                    let tag = normalize_tag(tag)?;
+                    if !file.tags.contains(&tag) {
+                        file.tags.push(tag);
+                    }
+                }
+            }
+            match item.get("symlink_target") {
+                None | Some(JsonValue::Null) => {}
+                Some(JsonValue::String(target)) => {
+                    file.is_symlink = true;
+                    file.symlink_target = Some(target.clone());
+                }
+                Some(_) => return Err(FileError::InvalidInput("Symlink target must be a string or null".to_string())),
+            }
+            fs.next_id = fs.next_id.max(id.saturating_add(1));
+            fs.push_file(file, dir);
+        }
+        Ok(fs)
+    }
+
+    /// Loads saved state from a JSON file on disk, upgrading it to the current
+    /// schema version if it was written in an older one
+    pub fn migrate(path: &Path) -> FileResult<FileSystem> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| FileError::from(e).with_context(format!("Reading '{}'", path.display())))?;
+        FileSystem::import_from_json(&text)
+    }
+
+    /// Writes every text and binary file to `dir/<path>`, creating `dir` and the
+    /// subdirectories on the way if needed. Failed writes don't stop the export;
+    /// they are reported together at the end.
+    pub fn export_to_disk(&self, dir: &Path) -> FileResult<()> {
+        let mut failures = Vec::new();
+        let mut subdirs: Vec<&String> = self.directories.keys().collect();
+        subdirs.sort();
+        for subdir in subdirs {
+            let target = dir.join(&subdir[1..]);
+            if let Err(e) = std::fs::create_dir_all(&target) {
+                return Err(FileError::from(e).with_context(format!("Creating '{}'", target.display())));
+            }
+        }
+
+        let contents = self.files
+            .iter()
+            .map(|f| (f.id, &f.name, f.content.as_bytes()))
+            .chain(self.binary_files.iter().map(|f| (f.id, &f.name, f.content.as_slice())));
+
+        for (id, name, bytes) in contents {
+            let path = join_path(self.directory_of(id), name);
+            if name.contains('\\') {
+                failures.push(format!("{}: not a valid file name on disk", path));
+                continue;
+            }
+            if let Err(e) = std::fs::write(dir.join(&path[1..]), bytes) {
+                failures.push(format!("{}: {}", path, FileError::from(e)));
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(FileError::InvalidInput(format!("Failed to export {}", failures.join("; "))))
+        }
+    }
+
+    /// Builds a filesystem from the files in `dir`, in name order. UTF-8 files become
+    /// text files and anything else becomes a binary file; subdirectories are skipped
+    /// and their names returned alongside the filesystem.
+    pub fn import_from_disk(dir: &Path) -> FileResult<(FileSystem, Vec<String>)> {
+        let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut fs = FileSystem::with_capacity(entries.len());
+        let mut skipped = Vec::new();
+        for entry in entries {
+            let name = entry.file_name().into_string().map_err(|name| {
+                FileError::InvalidInput(format!("File name {:?} is not valid UTF-8", name))
+            })?;
+            if entry.file_type()?.is_dir() {
+                skipped.push(name);
+                continue;
+            }
+
+            let bytes = std::fs::read(entry.path())
+                .map_err(|e| FileError::from(e).with_context(format!("Reading '{}'", name)))?;
+            match String::from_utf8(bytes) {
+                Ok(content) => fs.create_file(name, content)?,
+                Err(e) => fs.create_binary_file(name, e.into_bytes())?,
+            };
+        }
+        Ok((fs, skipped))
+    }
+
+    /// Exports all files as an RSS 2.0 feed, one `<item>` per file. Each item's
+    /// `<description>` holds only the first 200 characters of the content, so feed
+    /// readers show a summary; the full content goes in `<content:encoded>`, which
+    /// `import_rss` reads back.
+    pub fn export_rss(&self, title: &str, link: &str, description: &str) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<rss version=\"2.0\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n<channel>\n");
+        xml.push_str(&format!("  <title>{}</title>\n", rss::escape_xml(title)));
+        xml.push_str(&format!("  <link>{}</link>\n", rss::escape_xml(link)));
+        xml.push_str(&format!("  <description>{}</description>\n", rss::escape_xml(description)));
+
+        for file in &self.files {
+            let summary: String = file.content.chars().take(200).collect();
+            xml.push_str("  <item>\n");
+            xml.push_str(&format!("    <title>{}</title>\n", rss::escape_xml(&file.name)));
+            xml.push_str(&format!("    <description>{}</description>\n", rss::escape_xml(&summary)));
+            xml.push_str(&format!("    <content:encoded>{}</content:encoded>\n", rss::escape_xml(&file.content)));
+            xml.push_str(&format!("    <pubDate>{}</pubDate>\n", rss::format_rfc2822(file.created_at)));
+            xml.push_str(&format!("    <guid isPermaLink=\"false\">{}</guid>\n", file.id));
+            xml.push_str("  </item>\n");
+        }
+
+        xml.push_str("</channel>\n</rss>\n");
+        xml
+    }
+
+    /// Creates one file per RSS `<item>`, skipping items whose title is already taken.
+    /// Returns the number of files created.
+    pub fn import_rss(&mut self, rss_xml: &str) -> FileResult<usize> {
+        let mut imported = 0;
+
+        for item in rss::parse_items(rss_xml)? {
+            if item.title.trim().is_empty() || self.name_taken(&item.title) {
+                continue;
+            }
+            self.create_file(item.title, item.content)?;
+            imported += 1;
+        }
+
+        Ok(imported)
+    }
+
+    /// Checks every file name for potential problems, returning only files with warnings
+    pub fn lint_names(&self) -> Vec<(&File, Vec<LintWarning>)> {
+        let mut results: Vec<(&File, Vec<LintWarning>)> = self.files
+            .iter()
+            .map(|f| (f, LintWarning::check(&f.name)))
+            .filter(|(_, warnings)| !warnings.is_empty())
+            .collect();
+        results.sort_by_key(|(f, _)| f.id);
+        results
+    }
+
+    /// Renames files to resolve their lint warnings, returning the `(old, new)` names.
+    /// Locked files and files whose fixed name would collide with an existing file are left untouched.
+    pub fn fix_lint_warnings(&mut self) -> FileResult<Vec<(String, String)>> {
+        self.check_not_readonly()?;
+
+        let mut renamed = Vec::new();
+
+        for index in 0..self.files.len() {
+            let old_name = self.files[index].name.clone();
+            if self.files[index].check_writable().is_err() || LintWarning::check(&old_name).is_empty() {
+                continue;
+            }
+
+            let new_name = LintWarning::fix(&old_name);
+            let new_path = join_path(self.directory_of(self.files[index].id), &new_name);
+            if new_name.is_empty() || new_name == old_name || self.name_taken(&new_path) {
+                continue;
+            }
+
+            self.set_name(index, new_name.clone());
+            renamed.push((old_name, new_name));
+        }
+
+        Ok(renamed)
+    }
+}
+
+/// Drops a file's ID from its extension's entry, removing the entry once it is empty
+fn unindex_extension(ext_index: &mut HashMap<String, Vec<u32>>, file: &File) {
+    let Some(ext) = file.extension() else {
+        return;
+    };
+    if let Some(ids) = ext_index.get_mut(ext) {
+        ids.retain(|&id| id != file.id);
+        if ids.is_empty() {
+            ext_index.remove(ext);
+        }
+    }
+}
+
+/// Trims and lowercases a tag, rejecting empty tags
+fn normalize_tag(tag: &str) -> FileResult<String> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err(FileError::InvalidInput("Tag cannot be empty".to_string()));
+    }
+    Ok(tag)
+}
+
+/// Upgrades a V1 file entry to V2, backfilling `modified_at` from `created_at` and
+/// an empty `tags` list. Every V1 member is kept as it is.
+fn migrate_v1_file(item: &JsonValue) -> JsonValue {
+    let JsonValue::Object(members) = item else {
+        return item.clone();
+    };
+    let mut members = members.clone();
+    if item.get("modified_at").is_none()
+        && let Some(created_at) = item.get("created_at")
+    {
+        members.push(("modified_at".to_string(), created_at.clone()));
+    }
+    if item.get("tags").is_none() {
+        members.push(("tags".to_string(), JsonValue::Array(Vec::new())));
+    }
+    JsonValue::Object(members)
+}
+
+/// Matches `name` against a glob pattern supporting `*` and `?`
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| glob_match(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && glob_match(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_match(rest, &name[1..]),
+    }
+}
+
+/// Turns `path` into a normalized absolute path, resolving it against the directory `base`
+fn absolute_path(base: &str, path: &str) -> String {
+    let mut components: Vec<&str> = if path.starts_with('/') {
+        Vec::new()
+    } else {
+        base.split('/').filter(|c| !c.is_empty()).collect()
+    };
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    format!("/{}", components.join("/"))
+}
+
+/// Appends a file or directory name to an absolute directory path
+fn join_path(dir: &str, name: &str) -> String {
+    if dir == ROOT_DIR {
+        format!("/{}", name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+/// Splits an absolute path into its parent path and final component
+fn split_path(path: &str) -> (&str, &str) {
+    match path.rsplit_once('/') {
+        Some(("", name)) => (ROOT_DIR, name),
+        Some((parent, name)) => (parent, name),
+        None => (ROOT_DIR, path),
+    }
+}
+
+/// Whether `path` is `dir` itself or somewhere beneath it
+fn is_within(path: &str, dir: &str) -> bool {
+    path == dir || path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Turns the failures of a bulk operation into a result
+fn batch_result(errors: Vec<FileError>) -> FileResult<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(FileError::BatchError(errors))
+    }
+}
+
+/// Hashes file content for cheap change detection
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Estimates the syllables in an English word by counting vowel runs
+fn count_syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+
+    // A trailing silent 'e' ("make") doesn't form a syllable, but "-le" ("table") does
+    if word.ends_with('e') && !word.ends_with("le") && count > 1 {
+        count -= 1;
+    }
+
+    count.max(1)
+}
+
+/// Splits a single CSV line into fields, honouring double-quoted fields
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Joins fields into a CSV line, quoting fields that need it
+fn format_csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|field| {
+            if field.contains(',') || field.contains('"') {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+/// Indexing by ID panics if no text file has that ID; use `get_file_by_id` to handle
+/// missing files. There is deliberately no `IndexMut`, since writes must be validated.
+impl std::ops::Index<u32> for FileSystem {
+    type Output = File;
+
+    fn index(&self, id: u32) -> &File {
+        self.get_file_by_id(id).unwrap_or_else(|_| panic!("no file with ID {}", id))
+    }
+}
+
+/// Indexing by name panics if no text file has that name; use `get_file` to handle
+/// missing files. Symlinks are returned as-is rather than followed.
+impl std::ops::Index<&str> for FileSystem {
+    type Output = File;
+
+    fn index(&self, name: &str) -> &File {
+        self.find_file(name).unwrap_or_else(|_| panic!("no file named '{}'", name))
+    }
+}
+
+impl IntoIterator for FileSystem {
+    type Item = File;
+    type IntoIter = std::vec::IntoIter<File>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_files().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a FileSystem {
+    type Item = &'a File;
+    type IntoIter = std::slice::Iter<'a, File>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut FileSystem {
+    type Item = &'a mut File;
+    type IntoIter = std::slice::IterMut<'a, File>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Adds each file through `create_file`, so it gets a fresh ID and only its name
+/// and content carry over. Files the filesystem rejects, such as name collisions,
+/// are silently skipped.
+impl Extend<File> for FileSystem {
+    fn extend<I: IntoIterator<Item = File>>(&mut self, iter: I) {
+        for file in iter {
+            let _ = self.create_file(file.name, file.content.to_string());
+        }
+    }
+}
+
+impl FromIterator<File> for FileSystem {
+    fn from_iter<I: IntoIterator<Item = File>>(iter: I) -> Self {
+        let mut fs = FileSystem::new();
+        fs.extend(iter);
+        fs
     }
 }
@@ -0,0 +1,9 @@
+pub mod cli;
+pub mod diff;
+pub mod error;
+pub mod file;
+pub mod json;
+pub mod pipeline;
+pub mod rss;
+pub mod snapshot;
+pub mod stats;
@@ -1,13 +1,27 @@
-mod error;
-mod file;
-mod cli;
-
-use cli::CLI;
+use cli::cli::CLI;
+use std::env;
 use std::process;
 
 fn main() {
     let mut cli = CLI::new();
-    
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    // `--no-color` may appear anywhere before a `--`, after which words are taken literally
+    let literal = args.split_off(args.iter().position(|arg| arg == "--").unwrap_or(args.len()));
+    let count = args.len();
+    args.retain(|arg| arg != "--no-color");
+    if args.len() != count {
+        cli.set_color(false);
+    }
+    args.extend(literal);
+
+    if !args.is_empty() {
+        if cli.run_args(&args).is_err() {
+            process::exit(1);
+        }
+        return;
+    }
+
     if let Err(e) = cli.run() {
         eprintln!("Fatal error: {}", e);
         process::exit(1);
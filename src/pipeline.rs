@@ -0,0 +1,136 @@
+use crate::error::{FileError, FileResult};
+
+/// Line ending style used by `TransformStep::NormalizeLineEndings`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// A single content transformation in a `ContentPipeline`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransformStep {
+    TrimWhitespace,
+    Lowercase,
+    Uppercase,
+    NormalizeLineEndings(LineEnding),
+    StripBlankLines,
+    WrapAt(usize),
+    Rot13,
+}
+
+impl TransformStep {
+    /// Parses a step name such as `trim`, `lowercase`, `crlf` or `wrap:80`
+    pub fn parse(input: &str) -> FileResult<Self> {
+        let input = input.trim().to_lowercase();
+        match input.as_str() {
+            "trim" => Ok(TransformStep::TrimWhitespace),
+            "lowercase" | "lower" => Ok(TransformStep::Lowercase),
+            "uppercase" | "upper" => Ok(TransformStep::Uppercase),
+            "lf" => Ok(TransformStep::NormalizeLineEndings(LineEnding::Lf)),
+            "crlf" => Ok(TransformStep::NormalizeLineEndings(LineEnding::CrLf)),
+            "strip-blank" => Ok(TransformStep::StripBlankLines),
+            "rot13" => Ok(TransformStep::Rot13),
+            _ => match input.strip_prefix("wrap:") {
+                Some(width) => width
+                    .parse::<usize>()
+                    .map(TransformStep::WrapAt)
+                    .map_err(|_| FileError::InvalidInput(format!("Invalid wrap width: {}", width))),
+                None => Err(FileError::InvalidInput(format!("Unknown transform: {}", input))),
+            },
+        }
+    }
+
+    /// Applies this step to the input
+    pub fn apply(&self, input: &str) -> String {
+        match self {
+            TransformStep::TrimWhitespace => input.trim().to_string(),
+            TransformStep::Lowercase => input.to_lowercase(),
+            TransformStep::Uppercase => input.to_uppercase(),
+            TransformStep::NormalizeLineEndings(ending) => {
+                input.replace("\r\n", "\n").replace('\n', ending.as_str())
+            }
+            TransformStep::StripBlankLines => {
+                let ending = if input.contains("\r\n") { "\r\n" } else { "\n" };
+                input
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .collect::<Vec<_>>()
+                    .join(ending)
+            }
+            TransformStep::WrapAt(width) => input
+                .lines()
+                .map(|line| wrap_line(line, *width))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            TransformStep::Rot13 => input.chars().map(rot13).collect(),
+        }
+    }
+}
+
+/// An ordered list of transformations applied to file content
+#[derive(Debug, Clone, Default)]
+pub struct ContentPipeline {
+    steps: Vec<TransformStep>,
+}
+
+impl ContentPipeline {
+    pub fn new(steps: &[TransformStep]) -> Self {
+        ContentPipeline {
+            steps: steps.to_vec(),
+        }
+    }
+
+    /// Runs every step in order
+    pub fn apply(&self, input: &str) -> String {
+        self.steps
+            .iter()
+            .fold(input.to_string(), |content, step| step.apply(&content))
+    }
+
+    /// Gets the steps in this pipeline
+    pub fn steps(&self) -> &[TransformStep] {
+        &self.steps
+    }
+}
+
+/// Greedily wraps a single line at word boundaries; words longer than `width` stay whole
+fn wrap_line(line: &str, width: usize) -> String {
+    if width == 0 || line.chars().count() <= width {
+        return line.to_string();
+    }
+
+    let mut wrapped = Vec::new();
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            wrapped.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        wrapped.push(current);
+    }
+
+    wrapped.join("\n")
+}
+
+fn rot13(c: char) -> char {
+    match c {
+        'a'..='z' => (((c as u8 - b'a') + 13) % 26 + b'a') as char,
+        'A'..='Z' => (((c as u8 - b'A') + 13) % 26 + b'A') as char,
+        _ => c,
+    }
+}
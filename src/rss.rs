@@ -0,0 +1,182 @@
+use crate::error::{FileError, FileResult};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAY_NAMES: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Escapes the five XML special characters
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats a timestamp as an RFC 2822 date in UTC, e.g. `Thu, 01 Jan 1970 00:00:00 +0000`
+pub fn format_rfc2822(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = secs / 86_400;
+    let (year, month, day) = civil_from_days(days as i64);
+    let rem = secs % 86_400;
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} +0000",
+        DAY_NAMES[(days % 7) as usize],
+        day,
+        MONTH_NAMES[(month - 1) as usize],
+        year,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// Converts days since the Unix epoch into a `(year, month, day)` civil date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's days-to-civil algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// A feed item extracted by `parse_items`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RssItem {
+    pub title: String,
+    pub content: String,
+}
+
+/// Extracts every `<item>` from an RSS 2.0 document, preferring
+/// `<content:encoded>` over `<description>` for the item body
+pub fn parse_items(xml: &str) -> FileResult<Vec<RssItem>> {
+    let mut items = Vec::new();
+    let mut rest = xml;
+
+    while let Some(start) = find_open_tag(rest, "item") {
+        let body_start = rest[start..]
+            .find('>')
+            .map(|i| start + i + 1)
+            .ok_or_else(|| FileError::InvalidInput("Unterminated <item> tag".to_string()))?;
+        let body_end = rest[body_start..]
+            .find("</item>")
+            .map(|i| body_start + i)
+            .ok_or_else(|| FileError::InvalidInput("Missing </item> closing tag".to_string()))?;
+        let body = &rest[body_start..body_end];
+
+        let title = element_text(body, "title")?.unwrap_or_default();
+        let content = match element_text(body, "content:encoded")? {
+            Some(content) => content,
+            None => element_text(body, "description")?.unwrap_or_default(),
+        };
+        items.push(RssItem { title, content });
+
+        rest = &rest[body_end + "</item>".len()..];
+    }
+
+    Ok(items)
+}
+
+/// Finds `<tag>` or `<tag ...>`, skipping tags that merely share a prefix
+fn find_open_tag(xml: &str, tag: &str) -> Option<usize> {
+    let needle = format!("<{}", tag);
+    let mut offset = 0;
+    while let Some(i) = xml[offset..].find(&needle) {
+        let at = offset + i;
+        match xml[at + needle.len()..].chars().next() {
+            Some('>') | Some(' ') | Some('\t') | Some('\n') | Some('\r') | Some('/') => return Some(at),
+            _ => offset = at + needle.len(),
+        }
+    }
+    None
+}
+
+/// Gets the unescaped text of the first `<tag>` element, if present, keeping
+/// leading and trailing whitespace
+fn element_text(xml: &str, tag: &str) -> FileResult<Option<String>> {
+    let start = match find_open_tag(xml, tag) {
+        Some(start) => start,
+        None => return Ok(None),
+    };
+    let open_end = xml[start..]
+        .find('>')
+        .map(|i| start + i)
+        .ok_or_else(|| FileError::InvalidInput(format!("Unterminated <{}> tag", tag)))?;
+    if xml[..open_end].ends_with('/') {
+        return Ok(Some(String::new()));
+    }
+
+    let close = format!("</{}>", tag);
+    let end = xml[open_end..]
+        .find(&close)
+        .map(|i| open_end + i)
+        .ok_or_else(|| FileError::InvalidInput(format!("Missing {} closing tag", close)))?;
+
+    // Whitespace is content unless it only pads a CDATA section
+    let raw = &xml[open_end + 1..end];
+    match raw.trim().strip_prefix("<![CDATA[").and_then(|r| r.strip_suffix("]]>")) {
+        Some(cdata) => Ok(Some(cdata.to_string())),
+        None => Ok(Some(unescape_xml(raw))),
+    }
+}
+
+/// Reverses `escape_xml`, including numeric character references
+pub fn unescape_xml(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').and_then(|semi| {
+            let entity = &rest[1..semi];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, semi))
+        });
+
+        match decoded {
+            Some((c, semi)) => {
+                unescaped.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+    unescaped
+}
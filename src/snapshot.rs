@@ -0,0 +1,330 @@
+use crate::error::{FileError, FileResult};
+use crate::file::{FilePermissions, DEFAULT_MAX_VERSIONS, ROOT_DIR};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Marks the start of every snapshot
+pub const MAGIC: [u8; 4] = *b"FSN2";
+
+/// Marks the start of a snapshot in the original format, which held only the
+/// name, content, ID and `created_at` of each text file
+pub const LEGACY_MAGIC: [u8; 4] = *b"FSNP";
+
+/// A whole filesystem as stored in a snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub next_id: u32,
+    /// Absolute path of the current directory
+    pub cwd: String,
+    /// Absolute paths of every directory but the root
+    pub directories: Vec<String>,
+    pub files: Vec<SnapshotRecord>,
+    pub binary_files: Vec<BinaryRecord>,
+}
+
+/// One text file as stored in a snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotRecord {
+    pub id: u32,
+    /// Absolute path of the directory holding the file
+    pub directory: String,
+    pub name: String,
+    pub content: String,
+    pub created_at: SystemTime,
+    pub modified_at: SystemTime,
+    pub accessed_at: SystemTime,
+    pub symlink_target: Option<String>,
+    pub tags: Vec<String>,
+    pub locked: bool,
+    pub permissions: FilePermissions,
+    pub owner: String,
+    pub versions: Vec<(String, SystemTime)>,
+    pub max_versions: usize,
+}
+
+/// One binary file as stored in a snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinaryRecord {
+    pub id: u32,
+    /// Absolute path of the directory holding the file
+    pub directory: String,
+    pub name: String,
+    pub content: Vec<u8>,
+    pub created_at: SystemTime,
+    pub modified_at: SystemTime,
+}
+
+/// Encodes a snapshot as: magic, u32 `next_id`, the current directory, a u32 count of
+/// directory paths and the paths, then the text files and the binary files, each a u32
+/// count followed by the records.
+///
+/// A text record holds its directory, name and content, the u32 ID, `created_at`,
+/// `modified_at` and `accessed_at`, a u8 flag byte (1 = symlink, 2 = locked) followed
+/// by the symlink target if there is one, a u8 permission mask (4 = read, 2 = write,
+/// 1 = execute), the owner, a u32 count of tags and the tags, the u32 `max_versions`,
+/// and a u32 count of versions, each a content and the time it was written.
+///
+/// A binary record holds its directory, name and content, the u32 ID, `created_at`
+/// and `modified_at`.
+///
+/// Strings and byte contents are u32-length-prefixed, times are u64 seconds plus u32
+/// nanoseconds since the Unix epoch, and all integers are little-endian.
+pub fn encode(snapshot: &Snapshot) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&snapshot.next_id.to_le_bytes());
+    write_str(&mut bytes, &snapshot.cwd);
+
+    write_len(&mut bytes, snapshot.directories.len());
+    for directory in &snapshot.directories {
+        write_str(&mut bytes, directory);
+    }
+
+    write_len(&mut bytes, snapshot.files.len());
+    for record in &snapshot.files {
+        write_str(&mut bytes, &record.directory);
+        write_str(&mut bytes, &record.name);
+        write_str(&mut bytes, &record.content);
+        bytes.extend_from_slice(&record.id.to_le_bytes());
+        write_time(&mut bytes, record.created_at);
+        write_time(&mut bytes, record.modified_at);
+        write_time(&mut bytes, record.accessed_at);
+
+        let flags = u8::from(record.symlink_target.is_some()) | (u8::from(record.locked) << 1);
+        bytes.push(flags);
+        if let Some(target) = &record.symlink_target {
+            write_str(&mut bytes, target);
+        }
+
+        let permissions = &record.permissions;
+        bytes.push((u8::from(permissions.read) << 2) | (u8::from(permissions.write) << 1) | u8::from(permissions.execute));
+        write_str(&mut bytes, &record.owner);
+
+        write_len(&mut bytes, record.tags.len());
+        for tag in &record.tags {
+            write_str(&mut bytes, tag);
+        }
+
+        write_len(&mut bytes, record.max_versions);
+        write_len(&mut bytes, record.versions.len());
+        for (content, written_at) in &record.versions {
+            write_str(&mut bytes, content);
+            write_time(&mut bytes, *written_at);
+        }
+    }
+
+    write_len(&mut bytes, snapshot.binary_files.len());
+    for record in &snapshot.binary_files {
+        write_str(&mut bytes, &record.directory);
+        write_str(&mut bytes, &record.name);
+        write_bytes(&mut bytes, &record.content);
+        bytes.extend_from_slice(&record.id.to_le_bytes());
+        write_time(&mut bytes, record.created_at);
+        write_time(&mut bytes, record.modified_at);
+    }
+    bytes
+}
+
+/// Decodes a snapshot written by `encode`. Snapshots in the legacy format are
+/// accepted too, with their files in the root and every other field at its default.
+pub fn decode(bytes: &[u8]) -> FileResult<Snapshot> {
+    let mut reader = Reader { bytes, pos: 0 };
+
+    let snapshot = match reader.take(MAGIC.len())? {
+        magic if magic == MAGIC => decode_current(&mut reader)?,
+        magic if magic == LEGACY_MAGIC => decode_legacy(&mut reader)?,
+        _ => return Err(FileError::InvalidInput("Not a filesystem snapshot".to_string())),
+    };
+
+    if reader.pos != bytes.len() {
+        return Err(FileError::InvalidInput("Snapshot has trailing data".to_string()));
+    }
+    Ok(snapshot)
+}
+
+fn decode_current(reader: &mut Reader) -> FileResult<Snapshot> {
+    let next_id = reader.read_u32()?;
+    let cwd = reader.read_str()?;
+
+    let count = reader.read_u32()?;
+    let mut directories = Vec::new();
+    for _ in 0..count {
+        directories.push(reader.read_str()?);
+    }
+
+    let count = reader.read_u32()?;
+    let mut files = Vec::new();
+    for _ in 0..count {
+        let directory = reader.read_str()?;
+        let name = reader.read_str()?;
+        let content = reader.read_str()?;
+        let id = reader.read_u32()?;
+        let created_at = reader.read_time()?;
+        let modified_at = reader.read_time()?;
+        let accessed_at = reader.read_time()?;
+
+        let flags = reader.read_u8()?;
+        let symlink_target = if flags & 1 != 0 { Some(reader.read_str()?) } else { None };
+        let mask = reader.read_u8()?;
+        let permissions = FilePermissions {
+            read: mask & 4 != 0,
+            write: mask & 2 != 0,
+            execute: mask & 1 != 0,
+        };
+        let owner = reader.read_str()?;
+
+        let tag_count = reader.read_u32()?;
+        let mut tags = Vec::new();
+        for _ in 0..tag_count {
+            tags.push(reader.read_str()?);
+        }
+
+        let max_versions = reader.read_u32()? as usize;
+        let version_count = reader.read_u32()?;
+        let mut versions = Vec::new();
+        for _ in 0..version_count {
+            let content = reader.read_str()?;
+            versions.push((content, reader.read_time()?));
+        }
+
+        files.push(SnapshotRecord {
+            id,
+            directory,
+            name,
+            content,
+            created_at,
+            modified_at,
+            accessed_at,
+            symlink_target,
+            tags,
+            locked: flags & 2 != 0,
+            permissions,
+            owner,
+            versions,
+            max_versions,
+        });
+    }
+
+    let count = reader.read_u32()?;
+    let mut binary_files = Vec::new();
+    for _ in 0..count {
+        let directory = reader.read_str()?;
+        let name = reader.read_str()?;
+        let content = reader.read_bytes()?.to_vec();
+        let id = reader.read_u32()?;
+        let created_at = reader.read_time()?;
+        let modified_at = reader.read_time()?;
+        binary_files.push(BinaryRecord { id, directory, name, content, created_at, modified_at });
+    }
+
+    Ok(Snapshot { next_id, cwd, directories, files, binary_files })
+}
+
+fn decode_legacy(reader: &mut Reader) -> FileResult<Snapshot> {
+    let count = reader.read_u32()?;
+    let mut files = Vec::new();
+    for _ in 0..count {
+        let name = reader.read_str()?;
+        let content = reader.read_str()?;
+        let id = reader.read_u32()?;
+        let created_at = reader.read_time()?;
+
+        files.push(SnapshotRecord {
+            id,
+            directory: ROOT_DIR.to_string(),
+            name,
+            content,
+            created_at,
+            modified_at: created_at,
+            accessed_at: created_at,
+            symlink_target: None,
+            tags: Vec::new(),
+            locked: false,
+            permissions: FilePermissions::default(),
+            owner: String::new(),
+            versions: Vec::new(),
+            max_versions: DEFAULT_MAX_VERSIONS,
+        });
+    }
+
+    let next_id = files.iter().map(|f| f.id.saturating_add(1)).max().unwrap_or(1);
+    Ok(Snapshot {
+        next_id,
+        cwd: ROOT_DIR.to_string(),
+        directories: Vec::new(),
+        files,
+        binary_files: Vec::new(),
+    })
+}
+
+fn write_len(bytes: &mut Vec<u8>, len: usize) {
+    bytes.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn write_bytes(bytes: &mut Vec<u8>, data: &[u8]) {
+    write_len(bytes, data.len());
+    bytes.extend_from_slice(data);
+}
+
+fn write_str(bytes: &mut Vec<u8>, text: &str) {
+    write_bytes(bytes, text.as_bytes());
+}
+
+fn write_time(bytes: &mut Vec<u8>, time: SystemTime) {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    bytes.extend_from_slice(&since_epoch.as_secs().to_le_bytes());
+    bytes.extend_from_slice(&since_epoch.subsec_nanos().to_le_bytes());
+}
+
+/// Cursor over snapshot bytes that fails cleanly on truncated input
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> FileResult<&'a [u8]> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| FileError::InvalidInput("Snapshot is truncated".to_string()))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> FileResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u32(&mut self) -> FileResult<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_u64(&mut self) -> FileResult<u64> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn read_bytes(&mut self) -> FileResult<&'a [u8]> {
+        let len = self.read_u32()? as usize;
+        self.take(len)
+    }
+
+    fn read_str(&mut self) -> FileResult<String> {
+        let bytes = self.read_bytes()?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| FileError::InvalidInput("Snapshot contains invalid UTF-8".to_string()))
+    }
+
+    fn read_time(&mut self) -> FileResult<SystemTime> {
+        let secs = self.read_u64()?;
+        let nanos = self.read_u32()?;
+        if nanos >= 1_000_000_000 {
+            return Err(FileError::InvalidInput("Snapshot has an invalid timestamp".to_string()));
+        }
+        UNIX_EPOCH
+            .checked_add(Duration::new(secs, nanos))
+            .ok_or_else(|| FileError::InvalidInput("Snapshot has an invalid timestamp".to_string()))
+    }
+}
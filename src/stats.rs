@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+/// Core statistics for the text files of a `FileSystem`, computed by `FileSystem::get_stats`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileStats {
+    pub file_count: usize,
+    pub total_size: usize,
+    pub average_size_bytes: f64,
+    pub total_lines: usize,
+    pub total_words: usize,
+    /// ID of the largest text file; ties go to the lowest ID
+    pub largest_file_id: Option<u32>,
+    /// Text files per extension, with extensionless files under "no extension"
+    pub extension_counts: HashMap<String, usize>,
+}
+
+impl FileStats {
+    /// Extension counts ordered by extension, for stable output
+    pub fn sorted_extension_counts(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = self.extension_counts
+            .iter()
+            .map(|(ext, count)| (ext.as_str(), *count))
+            .collect();
+        counts.sort();
+        counts
+    }
+}
+
+/// Full statistics report for the text files of a `FileSystem`, computed by
+/// `FileSystem::stats_report`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsReport {
+    pub summary: FileStats,
+    /// Name and size of the largest file
+    pub largest: Option<(String, usize)>,
+    /// Name and size of the smallest file
+    pub smallest: Option<(String, usize)>,
+    /// Name of the most recently modified file
+    pub last_modified: Option<String>,
+    /// Name of the most recently created file
+    pub newest: Option<String>,
+    /// Name of the earliest created file
+    pub oldest: Option<String>,
+    pub empty_files: usize,
+    /// Mean Shannon entropy of file contents in bits per byte
+    pub entropy_avg: f64,
+}
+
+impl StatsReport {
+    /// Renders the report as indented plain text
+    pub fn to_text(&self) -> String {
+        let summary = &self.summary;
+        let mut lines = vec![
+            format!("  Total files: {}", summary.file_count),
+            format!("  Total size: {} bytes", summary.total_size),
+        ];
+
+        if summary.file_count > 0 {
+            lines.push(format!("  Average file size: {:.2} bytes", summary.average_size_bytes));
+            if let Some((name, size)) = &self.largest {
+                lines.push(format!("  Largest file: {} ({} bytes)", name, size));
+            }
+            if let Some((name, size)) = &self.smallest {
+                lines.push(format!("  Smallest file: {} ({} bytes)", name, size));
+            }
+            if let Some(name) = &self.last_modified {
+                lines.push(format!("  Most recently modified: {}", name));
+            }
+            if let Some(name) = &self.newest {
+                lines.push(format!("  Newest file: {}", name));
+            }
+            if let Some(name) = &self.oldest {
+                lines.push(format!("  Oldest file: {}", name));
+            }
+            lines.push(format!("  Empty files: {}", self.empty_files));
+            lines.push(format!("  Total lines: {}", summary.total_lines));
+            lines.push(format!("  Total words: {}", summary.total_words));
+            lines.push(format!("  Average entropy: {:.2} bits/byte", self.entropy_avg));
+
+            lines.push("  File types:".to_string());
+            for (ext, count) in summary.sorted_extension_counts() {
+                lines.push(format!("    .{}: {} files", ext, count));
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    /// Renders the report as a JSON object
+    pub fn to_json(&self) -> String {
+        let file_json = |file: &Option<(String, usize)>| match file {
+            Some((name, size)) => format!("{{\"name\":\"{}\",\"size\":{}}}", escape_json(name), size),
+            None => "null".to_string(),
+        };
+        let summary = &self.summary;
+        let extensions = summary
+            .sorted_extension_counts()
+            .into_iter()
+            .map(|(ext, count)| format!("\"{}\":{}", escape_json(ext), count))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let name_json = |name: &Option<String>| match name {
+            Some(name) => format!("\"{}\"", escape_json(name)),
+            None => "null".to_string(),
+        };
+
+        format!(
+            "{{\"file_count\":{},\"total_size\":{},\"avg_size\":{:.2},\"largest\":{},\"smallest\":{},\"last_modified\":{},\"newest\":{},\"oldest\":{},\"by_extension\":{{{}}},\"empty_files\":{},\"total_words\":{},\"total_lines\":{},\"entropy_avg\":{:.4}}}",
+            summary.file_count,
+            summary.total_size,
+            summary.average_size_bytes,
+            file_json(&self.largest),
+            file_json(&self.smallest),
+            name_json(&self.last_modified),
+            name_json(&self.newest),
+            name_json(&self.oldest),
+            extensions,
+            self.empty_files,
+            summary.total_words,
+            summary.total_lines,
+            self.entropy_avg
+        )
+    }
+}
+
+/// ID allocation details, computed by `FileSystem::generate_id_report`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdReport {
+    pub next_id: u32,
+    pub allocated: usize,
+    /// Previously issued IDs that no longer belong to a file
+    pub gaps: Vec<u32>,
+    pub max_id: u32,
+    /// Allocated IDs divided by issued IDs; 1.0 means no gaps
+    pub utilization: f64,
+}
+
+/// Computes the Shannon entropy of a byte string in bits per byte
+pub fn shannon_entropy(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+
+    let mut counts = [0usize; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+
+    let len = bytes.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Escapes a string for inclusion in a JSON string literal
+pub fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,218 @@
+use cli::cli::CLI;
+use cli::error::FileError;
+use std::io::Cursor;
+
+/// Runs `script` through the command loop and returns everything it printed
+fn run(script: &str) -> String {
+    let mut output = Vec::new();
+    let mut cli = CLI::new();
+    cli.run_with_reader(Cursor::new(script), &mut output).unwrap();
+    drop(cli);
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn commands_run_in_order_until_the_end_of_input() {
+    let output = run("create notes.txt hello\nread notes.txt\n");
+
+    assert!(output.starts_with("🗂️  Welcome"));
+    let created = output.find("notes.txt").unwrap();
+    let read = output.rfind("hello").unwrap();
+    assert!(created < read);
+    assert!(output.ends_with("👋 Goodbye!\n"));
+}
+
+#[test]
+fn quit_stops_the_loop_before_later_commands() {
+    let output = run("create a.txt first\nquit\ncreate b.txt second\n");
+
+    assert!(output.contains("a.txt"));
+    assert!(!output.contains("b.txt"));
+    assert_eq!(output.matches("file-cli> ").count(), 2);
+}
+
+#[test]
+fn errors_are_reported_without_ending_the_session() {
+    let output = run("frobnicate\nread missing.txt\ncreate ok.txt fine\nread ok.txt\n");
+
+    assert!(output.contains("Unknown command: frobnicate"));
+    assert!(output.contains("File 'missing.txt' not found"));
+    assert!(output.contains("fine"));
+}
+
+#[test]
+fn prompts_read_their_answers_from_the_same_reader() {
+    let output = run("create\nprompted.txt\nfrom the reader\nread prompted.txt\n");
+
+    assert!(output.contains("from the reader"));
+    assert!(!output.contains("❌"));
+}
+
+#[test]
+fn flags_can_follow_positional_arguments() {
+    let output = run(concat!(
+        "create a.csv \"h\\n10\\n9\\n100\"\n",
+        "csv-sort a.csv --col 0 --numeric --desc\n",
+        "read a.csv\n",
+        "create f \"one\\ntwo\\nthree\\nfour\\nfive\"\n",
+        "search-context f three -C 1\n",
+        "pad f 30 --char z\n",
+        "export-rss --title T --link http://x\n",
+    ));
+
+    assert!(output.contains("h\n100\n10\n9\n"));
+    assert!(output.contains("     2  two\n>    3  three\n     4  four\n--"));
+    assert!(!output.contains("   1  one"));
+    assert!(output.contains("fivezzzzzz"));
+    assert!(output.contains("<title>T</title>\n  <link>http://x</link>"));
+    assert!(!output.contains("❌"));
+}
+
+#[test]
+fn variadic_commands_take_every_argument() {
+    let output = run("create foo_a x\ncreate foo_b y\ncreate foo_c z\ncommon-prefix foo_a foo_b foo_c\ncreate a abc\ncreate b abc\ncreate c xyz\naverage a b c\n");
+
+    assert!(output.contains("Common prefix: 'foo_'"));
+    assert!(output.contains("----\nabc\n----"));
+}
+
+#[test]
+fn extra_arguments_and_unknown_flags_are_reported() {
+    let output = run("create Bad Name.TXT hello\nread --bogus Bad\nls\n");
+
+    assert!(output.contains("Unexpected extra argument 'hello'"));
+    assert!(output.contains("Unknown option '--bogus'"));
+    assert!(output.contains("No files found"));
+}
+
+#[test]
+fn lint_only_renames_files_when_given_fix() {
+    let output = run("create \"Bad Name.TXT\" x\nlint\nls\nlint --fix\nls\n");
+
+    let fixed_at = output.rfind("Linting file names").unwrap();
+    let (check, fixed) = output.split_at(fixed_at);
+    assert!(check.contains("Bad Name.TXT"));
+    assert!(check.contains("Run 'lint --fix'"));
+    assert!(!check.contains("Renamed"));
+    assert!(fixed.contains("Renamed 'Bad Name.TXT'"));
+    assert!(!output.contains("y/N"));
+}
+
+#[test]
+fn appending_blank_content_succeeds() {
+    let output = run("create e.txt hello\nappend e.txt \"\"\nappend e.txt\n\nread e.txt\n");
+
+    assert_eq!(output.matches("Content appended to 'e.txt' successfully").count(), 2);
+    assert!(!output.contains("❌"));
+    assert!(output.contains("----\nhello\n----"));
+}
+
+#[test]
+fn delete_finds_the_file_before_asking() {
+    let output = run("delete missing.txt\ncreate a.txt x\ncreate b.txt y\ndelete 2\ny\nls\n");
+
+    assert!(output.contains("File 'missing.txt' not found"));
+    assert_eq!(output.matches("[y/N]").count(), 1);
+    assert!(output.contains("Delete '/b.txt'? [y/N]"));
+    assert!(output.contains("File deleted successfully"));
+    assert!(!output.contains("[2] b.txt"));
+}
+
+#[test]
+fn custom_writers_are_uncolored_unless_color_is_requested() {
+    assert!(!run("create a.txt x\nread missing\n").contains('\x1b'));
+
+    let mut output = Vec::new();
+    let mut cli = CLI::new();
+    cli.set_color(true);
+    cli.run_with_reader(Cursor::new("create a.txt x\n"), &mut output).unwrap();
+    drop(cli);
+    assert!(String::from_utf8(output).unwrap().contains("\x1b[32m✅ File 'a.txt' created"));
+}
+
+#[test]
+fn execute_command_runs_a_single_line() {
+    let mut output = Vec::new();
+    let mut cli = CLI::new();
+    cli.set_output(Box::new(&mut output));
+
+    assert!(cli.execute_command("create a.txt hello").unwrap());
+    assert!(cli.execute_command("read a.txt").unwrap());
+    assert!(cli.execute_command("read missing.txt").is_err());
+    assert!(cli.execute_command("frobnicate").is_err());
+    assert!(!cli.execute_command("quit").unwrap());
+    drop(cli);
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("File 'a.txt' created"));
+    assert!(output.contains("----\nhello\n----"));
+    assert!(!output.contains("file-cli> "));
+}
+
+#[test]
+fn run_batch_runs_a_script_and_reports_failed_lines() {
+    let path = std::env::temp_dir().join(format!("cli_batch_{}.txt", std::process::id()));
+    std::fs::write(&path, "# setup\ncreate a.txt hello\n\nread missing.txt\nappend a.txt \" world\"\nquit\ncreate b.txt never\n").unwrap();
+
+    let mut output = Vec::new();
+    let mut cli = CLI::new();
+    cli.set_output(Box::new(&mut output));
+    let result = cli.run_batch(&path);
+    let after = cli.execute_command("read a.txt");
+    drop(cli);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(result, Err(FileError::BatchError(ref errors)) if errors.len() == 1));
+    assert!(after.is_ok());
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("Script finished: 3 succeeded, 1 failed"));
+    assert!(output.contains("Line 4: File 'missing.txt' not found"));
+    assert!(output.contains("hello world"));
+    assert!(!output.contains("b.txt"));
+}
+
+#[test]
+fn long_listings_are_paged() {
+    let creates: String = (1..=25).map(|n| format!("create f{:02}.txt x\n", n)).collect();
+
+    let output = run(&format!("{}ls\n\nls\nq\n", creates));
+
+    let listings: Vec<&str> = output.split("Files in /:").skip(1).collect();
+    assert_eq!(listings.len(), 2);
+    let (full, stopped) = (listings[0], listings[1]);
+    assert_eq!(full.matches("-- More --").count(), 1);
+    assert!(full.contains("f20.txt") && full.contains("f25.txt"));
+    assert!(stopped.contains("f20.txt") && !stopped.contains("f21.txt"));
+}
+
+#[test]
+fn heredocs_supply_multi_line_content() {
+    let output = run("create poem.txt\n<<END\n  first line\n\nlast line\nEND\nappend poem.txt <<EOF\n+ more\nEOF\nread poem.txt\n");
+
+    assert!(output.contains("----\n  first line\n\nlast line+ more\n----"));
+    assert!(!output.contains("❌"));
+}
+
+#[test]
+fn an_unterminated_heredoc_is_an_error() {
+    let output = run("create poem.txt\n<<END\nnever closed\n");
+
+    assert!(output.contains("Missing closing 'END' line"));
+    assert!(!output.contains("created"));
+}
+
+#[test]
+fn set_output_redirects_everything_to_the_writer() {
+    let mut output: Vec<u8> = Vec::new();
+    let mut cli = CLI::new();
+    cli.set_output(Box::new(&mut output));
+
+    cli.execute_command("create a.txt redirected").unwrap();
+    cli.execute_command("read a.txt").unwrap();
+    drop(cli);
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.starts_with("Creating file...\n✅ File 'a.txt' created"));
+    assert!(output.ends_with("----\nredirected\n----------------------------------------\n"));
+    assert!(!output.contains('\x1b'));
+}
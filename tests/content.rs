@@ -0,0 +1,328 @@
+use cli::error::FileError;
+use cli::file::FileSystem;
+use cli::pipeline::TransformStep;
+
+fn fs_with(files: &[(&str, &str)]) -> FileSystem {
+    let mut fs = FileSystem::new();
+    for (name, content) in files {
+        fs.create_file(name.to_string(), content.to_string()).unwrap();
+    }
+    fs
+}
+
+#[test]
+fn collected_results_are_left_out_of_later_searches() {
+    let mut fs = fs_with(&[("a.txt", "todo: one"), ("b.txt", "nothing"), ("c.txt", "todo: two\ntodo: three")]);
+
+    fs.search_and_collect("todo", "results.txt".to_string(), true).unwrap();
+
+    assert_eq!(
+        fs.read_file("results.txt").unwrap(),
+        "a.txt:1: todo: one\nc.txt:1: todo: two\nc.txt:2: todo: three"
+    );
+    let names: Vec<&str> = fs.search_content("todo", true).iter().map(|(f, _, _)| f.name.as_str()).collect();
+    assert_eq!(names, ["a.txt", "c.txt", "c.txt"]);
+    assert_eq!(fs.search_content("one", true).len(), 2);
+}
+
+#[test]
+fn replace_all_counts_by_absolute_path() {
+    let mut fs = fs_with(&[("a.txt", "old old"), ("b.txt", "new")]);
+    fs.mkdir("docs").unwrap();
+    fs.create_file("docs/a.txt".to_string(), "old".to_string()).unwrap();
+    fs.create_symlink("link".to_string(), "a.txt").unwrap();
+
+    let counts = fs.replace_all("old", "new").unwrap();
+
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort();
+    assert_eq!(counts, [("/a.txt".to_string(), 2), ("/docs/a.txt".to_string(), 1)]);
+    assert_eq!(fs.read_file("a.txt").unwrap(), "new new");
+    assert!(matches!(fs.replace_all("", "x"), Err(FileError::InvalidInput(_))));
+}
+
+#[test]
+fn csv_sort_keeps_the_header_and_sorts_numerically() {
+    let mut fs = fs_with(&[("data.csv", "name,age\nbob,10\namy,9\ncat,100\n")]);
+
+    fs.csv_sort("data.csv", 1, true, false).unwrap();
+    assert_eq!(fs.read_file("data.csv").unwrap(), "name,age\namy,9\nbob,10\ncat,100\n");
+
+    fs.csv_sort("data.csv", 1, false, false).unwrap();
+    assert_eq!(fs.read_file("data.csv").unwrap(), "name,age\nbob,10\ncat,100\namy,9\n");
+
+    fs.csv_sort("data.csv", 0, false, true).unwrap();
+    assert_eq!(fs.read_file("data.csv").unwrap(), "name,age\ncat,100\nbob,10\namy,9\n");
+}
+
+#[test]
+fn csv_sort_rejects_a_column_out_of_range() {
+    let mut fs = fs_with(&[("data.csv", "a,b\n1,2")]);
+
+    assert!(matches!(fs.csv_sort("data.csv", 2, false, false), Err(FileError::InvalidInput(_))));
+    assert_eq!(fs.read_file("data.csv").unwrap(), "a,b\n1,2");
+}
+
+#[test]
+fn common_prefix_of_file_names() {
+    let fs = fs_with(&[("foo_a", ""), ("foo_b", ""), ("foo_c", ""), ("bar", "")]);
+
+    assert_eq!(fs.find_longest_common_prefix(&["foo_a", "foo_b", "foo_c"]).unwrap(), "foo_");
+    assert_eq!(fs.find_longest_common_prefix(&["foo_a"]).unwrap(), "foo_a");
+    assert_eq!(fs.find_longest_common_prefix(&["foo_a", "bar"]).unwrap(), "");
+    assert!(fs.find_longest_common_prefix(&["foo_a", "missing"]).is_err());
+}
+
+#[test]
+fn batch_rename_substitutes_capture_groups() {
+    let mut fs = fs_with(&[("report_1.txt", "a"), ("report_2.txt", "b"), ("notes.txt", "c")]);
+
+    let mut renamed = fs.batch_rename(r"^report_(\d+)\.txt$", "summary_$1.txt").unwrap();
+    renamed.sort();
+
+    assert_eq!(renamed, vec![
+        ("report_1.txt".to_string(), "summary_1.txt".to_string()),
+        ("report_2.txt".to_string(), "summary_2.txt".to_string()),
+    ]);
+    assert_eq!(fs.read_file("summary_2.txt").unwrap(), "b");
+    assert!(fs.read_file("report_1.txt").is_err());
+    assert_eq!(fs.read_file("notes.txt").unwrap(), "c");
+}
+
+#[test]
+fn batch_rename_changes_nothing_on_a_collision() {
+    let mut fs = fs_with(&[("report_1.txt", "a"), ("report_2.txt", "b"), ("summary_2.txt", "taken")]);
+
+    assert!(matches!(fs.batch_rename(r"^report_(\d+)", "summary_$1"), Err(FileError::AlreadyExists(_))));
+    assert_eq!(fs.read_file("report_1.txt").unwrap(), "a");
+    assert_eq!(fs.read_file("summary_2.txt").unwrap(), "taken");
+}
+
+#[test]
+fn zip_files_pairs_by_position() {
+    let fs = fs_with(&[("a1", "1"), ("a2", "2"), ("a3", "3"), ("b1", "x"), ("b2", "y"), ("b3", "z")]);
+
+    let pairs = fs.zip_files(&["a1", "a2", "a3"], &["b1", "b2", "b3"]).unwrap();
+
+    let names: Vec<(&str, &str)> = pairs.iter().map(|(a, b)| (a.name.as_str(), b.name.as_str())).collect();
+    assert_eq!(names, vec![("a1", "b1"), ("a2", "b2"), ("a3", "b3")]);
+}
+
+#[test]
+fn zip_files_rejects_lists_of_different_lengths() {
+    let fs = fs_with(&[("a1", "1"), ("a2", "2"), ("b1", "x")]);
+
+    assert!(matches!(fs.zip_files(&["a1", "a2"], &["b1"]), Err(FileError::InvalidInput(_))));
+}
+
+#[test]
+fn sliding_window_search_includes_context_lines() {
+    let fs = fs_with(&[("log.txt", "one\ntwo\nthree\nfour\nfive match\nsix\nseven\neight")]);
+
+    let results = fs.sliding_window_search("log.txt", "match", 2).unwrap();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].line_number, 5);
+    assert_eq!(results[0].matched_line, "five match");
+    assert_eq!(results[0].before, vec!["three", "four"]);
+    assert_eq!(results[0].after, vec!["six", "seven"]);
+}
+
+#[test]
+fn sliding_window_search_clamps_context_at_the_start() {
+    let fs = fs_with(&[("log.txt", "match\ntwo\nthree")]);
+
+    let results = fs.sliding_window_search("log.txt", "match", 2).unwrap();
+
+    assert_eq!(results[0].line_number, 1);
+    assert!(results[0].before.is_empty());
+    assert_eq!(results[0].after, vec!["two", "three"]);
+}
+
+#[test]
+fn readability_score_follows_the_flesch_formula() {
+    // 9 one-syllable words in 2 sentences: 206.835 - 1.015 * 4.5 - 84.6 * 1.0 = 117.67
+    let fs = fs_with(&[("easy.txt", "The cat sat on the mat. The dog ran.")]);
+
+    let score = fs.compute_readability_score("easy.txt").unwrap();
+
+    assert!((score.score - 117.67).abs() <= 2.0, "score was {}", score.score);
+    assert_eq!(score.avg_words_per_sentence, 4.5);
+}
+
+#[test]
+fn readability_score_rejects_files_without_words() {
+    let fs = fs_with(&[("empty.txt", " ... ")]);
+
+    assert!(matches!(fs.compute_readability_score("empty.txt"), Err(FileError::InvalidInput(_))));
+}
+
+#[test]
+fn pad_to_size_reaches_the_exact_target() {
+    let mut fs = fs_with(&[("a.txt", "abc")]);
+
+    fs.pad_to_size("a.txt", 8, '.').unwrap();
+
+    assert_eq!(fs.read_file("a.txt").unwrap(), "abc.....");
+    assert_eq!(fs.get_file("a.txt").unwrap().size, 8);
+}
+
+#[test]
+fn pad_to_size_with_a_multibyte_char_never_falls_short() {
+    let mut fs = fs_with(&[("even.txt", "ab"), ("odd.txt", "ab")]);
+
+    fs.pad_to_size("even.txt", 6, 'é').unwrap();
+    fs.pad_to_size("odd.txt", 7, 'é').unwrap();
+
+    assert_eq!(fs.read_file("even.txt").unwrap(), "abéé");
+    assert_eq!(fs.get_file("even.txt").unwrap().size, 6);
+    assert_eq!(fs.read_file("odd.txt").unwrap(), "abééé");
+    assert_eq!(fs.get_file("odd.txt").unwrap().size, 8);
+}
+
+#[test]
+fn pad_to_size_at_the_current_size_is_a_no_op() {
+    let mut fs = fs_with(&[("a.txt", "abc")]);
+    let versions = fs.get_file("a.txt").unwrap().versions.len();
+
+    fs.pad_to_size("a.txt", 3, '.').unwrap();
+
+    assert_eq!(fs.read_file("a.txt").unwrap(), "abc");
+    assert_eq!(fs.get_file("a.txt").unwrap().versions.len(), versions);
+    assert!(matches!(fs.pad_to_size("a.txt", 2, '.'), Err(FileError::InvalidInput(_))));
+}
+
+#[test]
+fn each_pipeline_step_transforms_content() {
+    let cases = [
+        ("trim", "  hi \n", "hi"),
+        ("lower", "HeLLo", "hello"),
+        ("upper", "HeLLo", "HELLO"),
+        ("crlf", "a\nb\r\nc", "a\r\nb\r\nc"),
+        ("lf", "a\r\nb\r\n", "a\nb\n"),
+        ("strip-blank", "a\n\n  \nb", "a\nb"),
+        ("wrap:5", "aaa bbb ccc", "aaa\nbbb\nccc"),
+        ("rot13", "Hello!", "Uryyb!"),
+    ];
+
+    for (step, input, expected) in cases {
+        assert_eq!(TransformStep::parse(step).unwrap().apply(input), expected, "step {}", step);
+    }
+    assert!(TransformStep::parse("wrap:x").is_err());
+    assert!(TransformStep::parse("reverse").is_err());
+}
+
+#[test]
+fn pipeline_applies_steps_in_order() {
+    let mut fs = fs_with(&[("a.txt", "  Hello\n\nWorld  ")]);
+    let steps = ["trim", "strip-blank", "upper", "crlf"].map(|s| TransformStep::parse(s).unwrap());
+
+    fs.apply_pipeline("a.txt", &FileSystem::content_pipeline(&steps)).unwrap();
+
+    assert_eq!(fs.read_file("a.txt").unwrap(), "HELLO\r\nWORLD");
+}
+
+#[test]
+fn auto_tag_applies_each_heuristic() {
+    let mut fs = fs_with(&[
+        ("notes.md", "plain words"),
+        ("data.csv", "a,b"),
+        ("run.log", "started"),
+        ("main.rs", "fn main() {}"),
+        ("config", "{\"key\": 1}"),
+        ("page", "<HTML><body>hi</body>"),
+        ("alert", "STOP NOW"),
+        ("blank", ""),
+    ]);
+
+    let tags = |fs: &mut FileSystem, name: &str| fs.auto_tag(name).unwrap();
+    assert_eq!(tags(&mut fs, "notes.md"), vec!["text"]);
+    assert_eq!(tags(&mut fs, "data.csv"), vec!["csv"]);
+    assert_eq!(tags(&mut fs, "run.log"), vec!["log"]);
+    assert_eq!(tags(&mut fs, "main.rs"), vec!["code"]);
+    assert_eq!(tags(&mut fs, "config"), vec!["json"]);
+    assert_eq!(tags(&mut fs, "page"), vec!["html"]);
+    assert_eq!(tags(&mut fs, "alert"), vec!["shout"]);
+    assert_eq!(tags(&mut fs, "blank"), vec!["empty"]);
+}
+
+#[test]
+fn auto_tag_never_adds_a_tag_twice() {
+    let mut fs = fs_with(&[("LOUD.txt", "HELLO")]);
+    fs.add_tag("LOUD.txt", "text").unwrap();
+
+    assert_eq!(fs.auto_tag("LOUD.txt").unwrap(), vec!["shout"]);
+    assert!(fs.auto_tag("LOUD.txt").unwrap().is_empty());
+    assert_eq!(fs.get_file("LOUD.txt").unwrap().tags, vec!["text", "shout"]);
+}
+
+#[test]
+fn cross_reference_finds_every_file_mentioning_a_name() {
+    let fs = fs_with(&[
+        ("a.txt", "see b.txt"),
+        ("b.txt", "I am b.txt"),
+        ("c.txt", "b.txt is related"),
+        ("d.txt", "nothing here"),
+    ]);
+
+    let names: Vec<&str> = fs.cross_reference("b.txt").iter().map(|f| f.name.as_str()).collect();
+
+    assert_eq!(names, vec!["a.txt", "c.txt"]);
+    assert!(fs.cross_reference("d.txt").is_empty());
+}
+
+#[test]
+fn average_content_takes_the_majority_at_each_position() {
+    let fs = fs_with(&[("a", "abc"), ("b", "abc"), ("c", "xyz")]);
+
+    assert_eq!(fs.compute_average_content(&["a", "b", "c"]).unwrap(), "abc");
+}
+
+#[test]
+fn average_content_pads_shorter_files_with_spaces() {
+    let fs = fs_with(&[("short", "ab"), ("long", "abcd"), ("longer", "abcdef")]);
+
+    // Padding only wins a position once most files have ended
+    assert_eq!(fs.compute_average_content(&["short", "long", "longer"]).unwrap(), "abcd");
+    assert!(matches!(fs.compute_average_content(&[]), Err(FileError::InvalidInput(_))));
+}
+
+#[test]
+fn diff_renders_separate_hunks_with_context() {
+    let old: Vec<String> = (1..=12).map(|n| n.to_string()).collect();
+    let mut new = old.clone();
+    new[1] = "two".to_string();
+    new[10] = "eleven".to_string();
+    new.push("13".to_string());
+    let fs = fs_with(&[("old.txt", &old.join("\n")), ("new.txt", &new.join("\n"))]);
+
+    let expected = [
+        "--- old.txt", "+++ new.txt",
+        "@@ -1,5 +1,5 @@", " 1", "-2", "+two", " 3", " 4", " 5",
+        "@@ -8,5 +8,6 @@", " 8", " 9", " 10", "-11", "+eleven", " 12", "+13",
+    ];
+    assert_eq!(fs.diff("old.txt", "new.txt").unwrap(), expected.join("\n"));
+    assert_eq!(fs.diff("old.txt", "old.txt").unwrap(), "");
+}
+
+#[test]
+fn concat_undoes_split() {
+    for original in ["one\ntwo\nthree\nfour", "one\ntwo\nthree\n", "a\n\nb"] {
+        let mut fs = fs_with(&[("whole.txt", original)]);
+
+        fs.split_file("whole.txt", 2, "tail.txt".to_string()).unwrap();
+        fs.concat_files("whole.txt", "tail.txt", "joined.txt".to_string()).unwrap();
+
+        assert_eq!(fs.read_file("joined.txt").unwrap(), original);
+    }
+}
+
+#[test]
+fn split_rejects_lines_outside_the_file() {
+    let mut fs = fs_with(&[("a.txt", "one\ntwo"), ("taken.txt", "")]);
+
+    assert!(matches!(fs.split_file("a.txt", 0, "b.txt".to_string()), Err(FileError::InvalidInput(_))));
+    assert!(matches!(fs.split_file("a.txt", 2, "b.txt".to_string()), Err(FileError::InvalidInput(_))));
+    assert!(matches!(fs.split_file("a.txt", 1, "taken.txt".to_string()), Err(FileError::AlreadyExists(_))));
+    assert_eq!(fs.read_file("a.txt").unwrap(), "one\ntwo");
+}
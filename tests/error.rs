@@ -0,0 +1,52 @@
+use cli::error::FileError;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+#[test]
+fn display_includes_wrapped_errors_through_context() {
+    let error = FileError::from(io::Error::other("broken pipe")).with_context("Writing 'a.txt'");
+
+    assert_eq!(error.to_string(), "Writing 'a.txt': I/O error: broken pipe");
+    let external = FileError::Wrapped(Arc::new(fmt::Error));
+    assert_eq!(external.to_string(), format!("External error: {}", fmt::Error));
+    #[allow(deprecated)]
+    {
+        assert_eq!(error.report(), error.to_string());
+        assert_eq!(FileError::Io("disk full".to_string()).to_string(), "I/O error: disk full");
+    }
+}
+
+#[test]
+fn source_returns_the_wrapped_io_error() {
+    let error = FileError::from(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+
+    let source = error.source().expect("wrapped errors have a source");
+    let io_error = source.downcast_ref::<io::Error>().expect("the source is the original io::Error");
+    assert_eq!(io_error.kind(), io::ErrorKind::NotFound);
+    assert_eq!(io_error.to_string(), "no such file");
+}
+
+#[test]
+fn source_walks_from_context_to_the_original_error() {
+    let error = FileError::NotFound("a.txt".to_string()).with_context("Reading 'a.txt'");
+
+    let source = error.source().expect("contextual errors have a source");
+    assert!(matches!(source.downcast_ref::<FileError>(), Some(FileError::NotFound(name)) if name == "a.txt"));
+    assert!(source.source().is_none());
+    assert!(FileError::EmptyContent.source().is_none());
+}
+
+#[test]
+fn question_mark_converts_io_errors() {
+    fn read_missing() -> Result<String, FileError> {
+        Ok(std::fs::read_to_string("/definitely/not/a/real/path")?)
+    }
+
+    let error = read_missing().unwrap_err();
+    assert!(matches!(error, FileError::Wrapped(_)));
+    assert!(error.to_string().starts_with("I/O error: "));
+    let io_error = error.source().and_then(|e| e.downcast_ref::<io::Error>()).unwrap();
+    assert_eq!(io_error.kind(), io::ErrorKind::NotFound);
+}
@@ -0,0 +1,706 @@
+use cli::diff::DiffLine;
+use cli::error::FileError;
+use cli::file::{File, FileSystem, LintWarning, SortField, SortOrder, MAX_LINT_NAME_LEN};
+use cli::json::{self, JsonValue};
+use std::collections::HashMap;
+use std::time::{Duration, UNIX_EPOCH};
+
+fn fs_with(files: &[(&str, &str)]) -> FileSystem {
+    let mut fs = FileSystem::new();
+    for (name, content) in files {
+        fs.create_file(name.to_string(), content.to_string()).unwrap();
+    }
+    fs
+}
+
+#[test]
+fn deleting_a_file_removes_it_from_its_directory() {
+    let mut fs = FileSystem::new();
+    fs.mkdir("docs").unwrap();
+    let id = fs.create_file("docs/a.txt".to_string(), "a".to_string()).unwrap();
+
+    fs.delete_file("docs/a.txt").unwrap();
+
+    assert!(!fs.directory("docs").unwrap().files.contains(&id));
+    assert!(fs.verify_index_integrity());
+}
+
+#[test]
+fn bulk_removals_update_directory_listings() {
+    let mut fs = fs_with(&[("empty.txt", ""), ("keep.txt", "x"), ("scratch.tmp", "y")]);
+
+    assert_eq!(fs.delete_empty_files().unwrap(), 1);
+    assert_eq!(fs.retain(|f| !f.name.ends_with(".tmp")).unwrap(), 1);
+
+    let listed = &fs.directory("/").unwrap().files;
+    assert_eq!(listed, &vec![fs["keep.txt"].id]);
+    assert!(fs.verify_index_integrity());
+}
+
+#[test]
+fn create_with_a_path_places_the_file_in_that_directory() {
+    let mut fs = FileSystem::new();
+    fs.mkdir("docs").unwrap();
+
+    let id = fs.create_file("docs/a.txt".to_string(), "hello".to_string()).unwrap();
+
+    assert_eq!(fs.get_file_by_id(id).unwrap().name, "a.txt");
+    assert_eq!(fs.directory_of(id), "/docs");
+    assert!(matches!(
+        fs.create_file("missing/b.txt".to_string(), String::new()),
+        Err(FileError::NotFound(_))
+    ));
+}
+
+#[test]
+fn the_same_name_can_exist_in_different_directories() {
+    let mut fs = fs_with(&[("a.txt", "root")]);
+    fs.mkdir("docs").unwrap();
+    fs.create_file("docs/a.txt".to_string(), "docs".to_string()).unwrap();
+
+    fs.cd("docs").unwrap();
+    assert_eq!(fs.read_file("a.txt").unwrap(), "docs");
+    assert_eq!(fs.read_file("../a.txt").unwrap(), "root");
+    assert!(matches!(
+        fs.create_file("a.txt".to_string(), String::new()),
+        Err(FileError::AlreadyExists(_))
+    ));
+    assert!(fs.verify_index_integrity());
+}
+
+#[test]
+fn rename_can_move_a_file_between_directories() {
+    let mut fs = fs_with(&[("a.txt", "root")]);
+    fs.mkdir("docs").unwrap();
+
+    fs.rename_file("a.txt", "docs").unwrap();
+
+    assert!(fs.resolve_file_path("a.txt").is_err());
+    assert_eq!(fs.resolve_file_path("docs/a.txt").unwrap(), "/docs/a.txt");
+    assert_eq!(fs.directory("docs").unwrap().files.len(), 1);
+    assert!(fs.verify_index_integrity());
+}
+
+#[test]
+fn reading_a_symlink_reads_its_target() {
+    let mut fs = fs_with(&[("target.txt", "hello")]);
+    fs.create_symlink("link.txt".to_string(), "target.txt").unwrap();
+
+    assert_eq!(fs.read_file("link.txt").unwrap(), "hello");
+    fs.write_file("link.txt", "changed".to_string()).unwrap();
+    assert_eq!(fs.read_file("target.txt").unwrap(), "changed");
+}
+
+#[test]
+fn symlinks_that_would_form_a_cycle_are_rejected() {
+    let mut fs = FileSystem::new();
+    fs.create_symlink("a".to_string(), "b").unwrap();
+
+    assert!(matches!(fs.create_symlink("b".to_string(), "a"), Err(FileError::InvalidInput(_))));
+}
+
+#[test]
+fn renaming_a_symlink_to_close_a_cycle_is_rejected() {
+    let mut fs = FileSystem::new();
+    fs.create_symlink("a".to_string(), "b").unwrap();
+    fs.create_symlink("c".to_string(), "a").unwrap();
+
+    assert!(matches!(fs.rename_file("c", "b"), Err(FileError::InvalidInput(_))));
+    fs.create_symlink("z".to_string(), "a").unwrap();
+    assert!(fs.resolve_file_path("c").is_ok());
+}
+
+#[test]
+fn an_existing_cycle_does_not_hang_symlink_creation() {
+    let mut fs = FileSystem::new();
+    fs.create_symlink("a".to_string(), "b").unwrap();
+    fs.create_symlink("b".to_string(), "c").unwrap();
+    fs.get_file_mut("b").unwrap().symlink_target = Some("a".to_string());
+
+    fs.create_symlink("z".to_string(), "a").unwrap();
+    assert!(matches!(fs.read_file("z"), Err(FileError::InvalidInput(_))));
+}
+
+#[test]
+fn every_growing_operation_respects_the_size_limit() {
+    let mut fs = FileSystem::with_max_total_size(10);
+    fs.create_file("a.txt".to_string(), "123456".to_string()).unwrap();
+
+    let exceeded = |result: Result<_, FileError>| matches!(result, Err(FileError::SizeLimitExceeded { .. }));
+    assert!(exceeded(fs.pad_to_size("a.txt", 11, 'x')));
+    assert!(exceeded(fs.hardlink_file("a.txt", "b.txt".to_string()).map(|_| ())));
+    assert!(exceeded(fs.create_binary_file("c.bin".to_string(), vec![0; 5]).map(|_| ())));
+
+    fs.create_binary_file("d.bin".to_string(), vec![0; 4]).unwrap();
+    assert!(exceeded(fs.write_binary_file("d.bin", vec![0; 5])));
+    assert_eq!(fs.total_size(), 10);
+}
+
+#[test]
+fn refreshing_an_index_that_outgrows_the_limit_fails() {
+    let mut fs = FileSystem::with_max_total_size(20);
+    fs.create_index_file("index.md".to_string()).unwrap();
+    fs.create_file("a.txt".to_string(), "123".to_string()).unwrap();
+
+    assert!(matches!(fs.refresh_index(), Err(FileError::SizeLimitExceeded { .. })));
+    assert_eq!(fs.read_file("index.md").unwrap(), "");
+}
+
+#[test]
+fn every_kind_of_file_counts_against_max_files() {
+    let mut fs = FileSystem::with_max_files(3);
+    fs.create_file("a.txt".to_string(), "a".to_string()).unwrap();
+    fs.create_binary_file("b.bin".to_string(), vec![1]).unwrap();
+    assert_eq!(fs.remaining_capacity(), Some(1));
+    fs.create_symlink("link".to_string(), "a.txt").unwrap();
+
+    let quota = |result: Result<u32, FileError>| matches!(result, Err(FileError::QuotaExceeded(3)));
+    assert!(quota(fs.create_file("c.txt".to_string(), String::new())));
+    assert!(quota(fs.create_symlink("link2".to_string(), "a.txt")));
+    assert!(quota(fs.hardlink_file("a.txt", "hard.txt".to_string())));
+    assert!(quota(fs.create_binary_file("d.bin".to_string(), Vec::new())));
+    assert_eq!(fs.remaining_capacity(), Some(0));
+}
+
+#[test]
+fn bulk_mutations_are_denied_on_a_readonly_filesystem() {
+    let mut fs = fs_with(&[("empty.txt", ""), ("bad name.TXT", "x")]);
+    fs.set_readonly(true);
+
+    fn denied<T>(result: Result<T, FileError>) -> bool {
+        matches!(result, Err(FileError::AccessDenied(_)))
+    }
+    assert!(denied(fs.retain(|_| false)));
+    assert!(denied(fs.delete_empty_files()));
+    assert!(denied(fs.delete_by_owner("nobody")));
+    assert!(denied(fs.compact()));
+    assert!(denied(fs.auto_tag_all()));
+    assert!(denied(fs.fix_lint_warnings()));
+    assert!(denied(fs.clear()));
+    assert_eq!(fs.file_count(), 2);
+}
+
+#[test]
+fn indexing_by_id_finds_files_after_earlier_deletions() {
+    let mut fs = fs_with(&[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")]);
+    fs.delete_file("a.txt").unwrap();
+
+    assert_eq!(fs[3].name, "c.txt");
+    assert_eq!(fs[2].name, "b.txt");
+}
+
+#[test]
+#[should_panic(expected = "no file with ID 1")]
+fn indexing_a_missing_id_panics() {
+    let mut fs = fs_with(&[("a.txt", "a")]);
+    fs.delete_file("a.txt").unwrap();
+    let _ = &fs[1];
+}
+
+#[test]
+fn indexing_by_name_resolves_paths_without_following_symlinks() {
+    let mut fs = fs_with(&[("a.txt", "a")]);
+    fs.mkdir("docs").unwrap();
+    fs.create_file("docs/b.txt".to_string(), "b".to_string()).unwrap();
+    fs.create_symlink("link".to_string(), "a.txt").unwrap();
+
+    assert_eq!(*fs["a.txt"].content, "a");
+    assert_eq!(fs["docs/b.txt"].id, 2);
+    assert!(fs["link"].is_symlink);
+    fs.cd("docs").unwrap();
+    assert_eq!(fs["b.txt"].name, "b.txt");
+}
+
+#[test]
+#[should_panic(expected = "no file named")]
+fn indexing_a_missing_name_panics() {
+    let fs = fs_with(&[("a.txt", "a")]);
+    let _ = &fs["b.txt"];
+}
+
+#[test]
+fn hard_links_share_content_until_one_is_written() {
+    let mut fs = fs_with(&[("a.txt", "shared")]);
+    fs.hardlink_file("a.txt", "b.txt".to_string()).unwrap();
+
+    assert!(fs.is_hardlinked("a.txt", "b.txt"));
+    assert_eq!(fs.read_file("b.txt").unwrap(), "shared");
+
+    fs.write_file("b.txt", "changed".to_string()).unwrap();
+
+    assert!(!fs.is_hardlinked("a.txt", "b.txt"));
+    assert_eq!(fs.read_file("a.txt").unwrap(), "shared");
+    assert_eq!(fs.read_file("b.txt").unwrap(), "changed");
+}
+
+#[test]
+fn deleting_one_hard_link_keeps_the_other() {
+    let mut fs = fs_with(&[("a.txt", "shared")]);
+    fs.hardlink_file("a.txt", "b.txt".to_string()).unwrap();
+
+    fs.delete_file("a.txt").unwrap();
+
+    assert_eq!(fs.read_file("b.txt").unwrap(), "shared");
+}
+
+#[test]
+fn stats_report_text_and_json_agree() {
+    let mut fs = fs_with(&[("a.txt", "ab"), ("b.md", ""), ("c.txt", "aaaa")]);
+    fs.create_binary_file("d.bin".to_string(), vec![0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+    for (id, secs) in [(1, 300), (2, 200), (3, 100)] {
+        let file = fs.get_file_by_id_mut(id).unwrap();
+        file.created_at = UNIX_EPOCH + Duration::from_secs(secs);
+        file.modified_at = UNIX_EPOCH + Duration::from_secs(secs * 2);
+    }
+
+    let report = fs.stats_report();
+
+    assert_eq!(report.summary.file_count, 3);
+    assert_eq!(report.summary.total_size, 6);
+    assert_eq!(report.summary.average_size_bytes, 2.0);
+    assert_eq!(report.summary.total_lines, 2);
+    assert_eq!(report.summary.total_words, 2);
+    assert_eq!(report.largest, Some(("c.txt".to_string(), 4)));
+    assert_eq!(report.smallest, Some(("b.md".to_string(), 0)));
+    assert_eq!(report.last_modified.as_deref(), Some("a.txt"));
+    assert_eq!(report.newest.as_deref(), Some("a.txt"));
+    assert_eq!(report.oldest.as_deref(), Some("c.txt"));
+    assert_eq!(report.empty_files, 1);
+    assert!((report.entropy_avg - 1.0 / 3.0).abs() < 1e-9);
+
+    let text = report.to_text();
+    let json = json::parse(&report.to_json()).unwrap();
+    let number = |key: &str| match json.get(key) {
+        Some(JsonValue::Number(n)) => *n,
+        other => panic!("{} was {:?}", key, other),
+    };
+    assert!(text.contains(&format!("Total files: {}", number("file_count"))));
+    assert!(text.contains(&format!("Total size: {} bytes", number("total_size"))));
+    assert!(text.contains(&format!("Average file size: {:.2} bytes", number("avg_size"))));
+    assert!(text.contains(&format!("Empty files: {}", number("empty_files"))));
+    assert!(text.contains(&format!("Average entropy: {:.2} bits/byte", number("entropy_avg"))));
+    let largest = json.get("largest").unwrap();
+    assert!(text.contains(&format!(
+        "Largest file: {} ({} bytes)",
+        largest.get("name").unwrap().as_str().unwrap(),
+        largest.get("size").unwrap().as_u64().unwrap()
+    )));
+    assert!(text.contains(&format!("Oldest file: {}", json.get("oldest").unwrap().as_str().unwrap())));
+    assert_eq!(json.get("by_extension").unwrap().get("txt").unwrap().as_u64(), Some(2));
+    assert!(json.get("by_extension").unwrap().get("bin").is_none());
+    assert!(text.contains(".txt: 2 files"));
+}
+
+#[test]
+fn id_report_lists_gaps_left_by_deletions() {
+    let mut fs = fs_with(&[("1.txt", ""), ("2.txt", ""), ("3.txt", ""), ("4.txt", ""), ("5.txt", "")]);
+    fs.delete_file("2.txt").unwrap();
+    fs.delete_file("4.txt").unwrap();
+
+    let report = fs.generate_id_report();
+
+    assert_eq!(report.gaps, vec![2, 4]);
+    assert_eq!(report.allocated, 3);
+    assert_eq!(report.max_id, 5);
+    assert_eq!(report.next_id, 6);
+    assert!((report.utilization - 0.6).abs() < 1e-9);
+}
+
+#[test]
+fn id_report_of_an_empty_filesystem_is_fully_utilized() {
+    let report = FileSystem::new().generate_id_report();
+
+    assert!(report.gaps.is_empty());
+    assert_eq!(report.allocated, 0);
+    assert_eq!(report.utilization, 1.0);
+}
+
+#[test]
+fn watcher_reports_each_change_once() {
+    let mut fs = fs_with(&[("a.txt", "one\ntwo")]);
+    let mut watcher = fs.watch_file("a.txt").unwrap();
+    assert!(!watcher.has_changed(&fs).unwrap());
+
+    fs.write_file("a.txt", "one\nthree".to_string()).unwrap();
+
+    assert!(watcher.has_changed(&fs).unwrap());
+    assert!(!watcher.has_changed(&fs).unwrap());
+}
+
+#[test]
+fn watcher_diff_lists_changed_lines() {
+    let mut fs = fs_with(&[("a.txt", "one\ntwo")]);
+    let mut watcher = fs.watch_file("a.txt").unwrap();
+
+    fs.write_file("a.txt", "one\nthree".to_string()).unwrap();
+
+    assert_eq!(watcher.diff(&fs).unwrap(), Some(vec![
+        DiffLine::Same("one".to_string()),
+        DiffLine::Removed("two".to_string()),
+        DiffLine::Added("three".to_string()),
+    ]));
+    fs.write_file("a.txt", "one\nthree".to_string()).unwrap();
+    assert_eq!(watcher.diff(&fs).unwrap(), None);
+}
+
+#[test]
+fn lint_flags_names_over_the_length_limit() {
+    let name = format!("{}.txt", "a".repeat(MAX_LINT_NAME_LEN - 3));
+    assert_eq!(LintWarning::check(&name), vec![LintWarning::TooLong]);
+    assert!(LintWarning::check(&name[1..]).is_empty());
+}
+
+#[test]
+fn lint_flags_spaces() {
+    assert_eq!(LintWarning::check("my notes.txt"), vec![LintWarning::ContainsSpaces]);
+}
+
+#[test]
+fn lint_flags_special_characters() {
+    assert_eq!(LintWarning::check("notes#1.txt"), vec![LintWarning::ContainsSpecialChars]);
+    assert!(LintWarning::check("my_notes-1.txt").is_empty());
+}
+
+#[test]
+fn lint_flags_all_uppercase_names() {
+    assert_eq!(LintWarning::check("README.TXT"), vec![LintWarning::UppercaseOnly]);
+    assert!(LintWarning::check("README.md").is_empty());
+    assert!(LintWarning::check("2024.csv").is_empty());
+}
+
+#[test]
+fn lint_flags_double_extensions() {
+    assert_eq!(LintWarning::check("archive.tar.gz"), vec![LintWarning::DoubleExtension]);
+}
+
+#[test]
+fn lint_flags_leading_dots() {
+    assert_eq!(LintWarning::check(".env"), vec![LintWarning::StartsWithDot]);
+}
+
+#[test]
+fn lint_flags_trailing_dots() {
+    assert_eq!(LintWarning::check("notes."), vec![LintWarning::EndsWithDot]);
+}
+
+#[test]
+fn fix_lint_warnings_renames_to_clean_names() {
+    let long = format!("{}.txt", "x".repeat(70));
+    let mut fs = fs_with(&[
+        ("my notes.txt", ""),
+        ("REPORT.TXT", ""),
+        ("archive.tar.gz", ""),
+        (".hidden.md", ""),
+        ("draft?.txt", ""),
+        (&long, ""),
+        ("clean.txt", ""),
+        ("taken one.txt", ""),
+        ("taken_one.txt", ""),
+    ]);
+
+    let renamed = fs.fix_lint_warnings().unwrap();
+
+    let pair = |old: &str, new: &str| (old.to_string(), new.to_string());
+    assert_eq!(renamed, vec![
+        pair("my notes.txt", "my_notes.txt"),
+        pair("REPORT.TXT", "report.txt"),
+        pair("archive.tar.gz", "archive_tar.gz"),
+        pair(".hidden.md", "hidden.md"),
+        pair("draft?.txt", "draft_.txt"),
+        pair(&long, &format!("{}.txt", "x".repeat(MAX_LINT_NAME_LEN - 4))),
+    ]);
+    for (_, new) in &renamed {
+        assert!(LintWarning::check(new).is_empty(), "{} still has warnings", new);
+    }
+    assert!(fs.get_file("taken one.txt").is_ok());
+    assert!(fs.get_file("clean.txt").is_ok());
+}
+
+#[test]
+fn index_file_lists_every_other_file_sorted_by_name() {
+    let mut fs = fs_with(&[("notes.md", "hello"), ("b.txt", "ab"), ("Makefile", "")]);
+
+    fs.create_index_file("index.md".to_string()).unwrap();
+
+    assert_eq!(
+        fs.read_file("index.md").unwrap(),
+        "3. Makefile (0 bytes) - none\n2. b.txt (2 bytes) - txt\n1. notes.md (5 bytes) - md"
+    );
+
+    fs.delete_file("b.txt").unwrap();
+    fs.refresh_index().unwrap();
+    assert_eq!(fs.read_file("index.md").unwrap(), "3. Makefile (0 bytes) - none\n1. notes.md (5 bytes) - md");
+}
+
+#[test]
+fn index_file_follows_changes_without_refresh_index() {
+    let mut fs = fs_with(&[("a.txt", "x")]);
+    fs.create_index_file("index.md".to_string()).unwrap();
+
+    fs.create_file("b.txt".to_string(), "yy".to_string()).unwrap();
+    assert_eq!(fs.read_file("index.md").unwrap(), "1. a.txt (1 bytes) - txt\n3. b.txt (2 bytes) - txt");
+
+    fs.append_file("a.txt", "z").unwrap();
+    fs.rename_file("b.txt", "c.md").unwrap();
+    fs.rename_file("index.md", "toc.md").unwrap();
+    assert_eq!(fs.read_file("toc.md").unwrap(), "1. a.txt (2 bytes) - txt\n3. c.md (2 bytes) - md");
+
+    fs.delete_file("a.txt").unwrap();
+    assert_eq!(fs.read_file("toc.md").unwrap(), "3. c.md (2 bytes) - md");
+}
+
+#[test]
+fn upsert_creates_then_overwrites_without_already_exists() {
+    let mut fs = FileSystem::new();
+
+    let created = fs.upsert_file("a.txt".to_string(), "first".to_string()).unwrap();
+    let updated = fs.upsert_file("a.txt".to_string(), "second".to_string()).unwrap();
+
+    assert_eq!(created, updated);
+    assert_eq!(fs.file_count(), 1);
+    assert_eq!(fs.read_file("a.txt").unwrap(), "second");
+    assert_eq!(fs.get_file("a.txt").unwrap().versions.len(), 1);
+}
+
+#[test]
+fn files_compare_equal_by_name_and_content_across_filesystems() {
+    let mut first = fs_with(&[("padding.txt", ""), ("a.txt", "same")]);
+    let mut second = fs_with(&[("a.txt", "same"), ("b.txt", "same")]);
+    first.add_tag("a.txt", "draft").unwrap();
+
+    let a1 = first.get_file("a.txt").unwrap().clone();
+    assert_ne!(a1.id, second.get_file("a.txt").unwrap().id);
+    assert_eq!(&a1, second.get_file("a.txt").unwrap());
+    assert_ne!(&a1, second.get_file("b.txt").unwrap());
+
+    second.write_file("a.txt", "changed".to_string()).unwrap();
+    assert_ne!(&a1, second.get_file("a.txt").unwrap());
+}
+
+#[test]
+fn files_of_the_same_size_sort_deterministically() {
+    let fs = fs_with(&[("c.txt", "xx"), ("a.txt", "yy"), ("big.txt", "zzzz"), ("b.txt", "ww")]);
+
+    let names = |files: Vec<&File>| files.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(fs.sorted_files(SortField::Size, SortOrder::Asc)), ["c.txt", "a.txt", "b.txt", "big.txt"]);
+    assert_eq!(names(fs.sorted_files(SortField::Size, SortOrder::Desc)), ["big.txt", "b.txt", "a.txt", "c.txt"]);
+
+    let mut files: Vec<&File> = fs.iter().collect();
+    files.sort();
+    assert_eq!(names(files), ["a.txt", "b.txt", "c.txt", "big.txt"]);
+}
+
+#[test]
+fn size_range_includes_both_boundaries() {
+    let fs = fs_with(&[("1", "a"), ("2", "ab"), ("3", "abc"), ("4", "abcd")]);
+
+    let names = |files: Vec<&File>| files.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(fs.filter_by_size_range(2, 3).unwrap()), ["2", "3"]);
+    assert_eq!(names(fs.filter_by_size_range(4, 4).unwrap()), ["4"]);
+    assert!(fs.filter_by_size_range(5, 10).unwrap().is_empty());
+    assert!(matches!(fs.filter_by_size_range(3, 2), Err(FileError::InvalidInput(_))));
+}
+
+#[test]
+fn average_size_keeps_the_fraction() {
+    let fs = fs_with(&[("a", "a"), ("b", "ab")]);
+
+    assert_eq!(fs.average_size(), 1.5);
+    assert_eq!(FileSystem::new().average_size(), 0.0);
+}
+
+#[test]
+fn iter_visits_every_file() {
+    let fs = fs_with(&[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")]);
+
+    let names: Vec<&str> = fs.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+
+    let mut total = 0;
+    for file in &fs {
+        total += file.size;
+    }
+    assert_eq!(total, 3);
+}
+
+#[test]
+fn into_iter_yields_owned_files() {
+    let mut fs = fs_with(&[("a.txt", "a"), ("b.txt", "b")]);
+    fs.create_binary_file("c.bin".to_string(), vec![0]).unwrap();
+
+    let files: Vec<File> = fs.into_iter().collect();
+
+    let names: Vec<String> = files.into_iter().map(|f| f.name).collect();
+    assert_eq!(names, ["a.txt", "b.txt"]);
+}
+
+#[test]
+fn retain_removes_files_failing_the_predicate() {
+    let mut fs = fs_with(&[("a.tmp", ""), ("keep.txt", "k"), ("b.tmp", ""), ("locked.tmp", "")]);
+    fs.lock_file("locked.tmp").unwrap();
+
+    let removed = fs.retain(|f| f.extension() != Some("tmp")).unwrap();
+
+    assert_eq!(removed, 2);
+    let names: Vec<&str> = fs.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["keep.txt", "locked.tmp"]);
+    assert!(fs.verify_index_integrity());
+}
+
+#[test]
+fn clear_removes_everything_and_restarts_ids() {
+    let mut fs = fs_with(&[("a.txt", "a"), ("b.txt", "b")]);
+    fs.create_binary_file("c.bin".to_string(), vec![0]).unwrap();
+
+    assert_eq!(fs.clear().unwrap(), 3);
+    assert_eq!(fs.file_count(), 0);
+    assert_eq!(fs.create_file("d.txt".to_string(), String::new()).unwrap(), 1);
+}
+
+#[test]
+fn bulk_removals_all_keep_locked_files() {
+    let mut fs = fs_with(&[("empty.txt", ""), ("a.txt", "a")]);
+    fs.mkdir("docs").unwrap();
+    fs.create_file_owned("docs/mine.txt".to_string(), "m".to_string(), "alice".to_string()).unwrap();
+    fs.create_binary_file("c.bin".to_string(), vec![0]).unwrap();
+    for name in ["empty.txt", "a.txt", "docs/mine.txt"] {
+        fs.lock_file(name).unwrap();
+    }
+
+    assert_eq!(fs.delete_empty_files().unwrap(), 0);
+    assert_eq!(fs.delete_by_owner("alice").unwrap(), 0);
+    assert_eq!(fs.retain(|_| false).unwrap(), 0);
+    assert_eq!(fs.clear().unwrap(), 1);
+
+    assert_eq!(fs.file_count(), 3);
+    assert_eq!(fs.directory_of(fs["docs/mine.txt"].id), "/docs");
+    assert_eq!(fs.create_file("d.txt".to_string(), String::new()).unwrap(), 5);
+    assert!(fs.verify_index_integrity());
+}
+
+#[test]
+fn clearing_an_empty_filesystem_is_a_no_op() {
+    let mut fs = FileSystem::new();
+
+    assert_eq!(fs.clear().unwrap(), 0);
+    assert_eq!(fs.file_count(), 0);
+    assert_eq!(fs.generate_id_report().next_id, 1);
+}
+
+#[test]
+fn with_capacity_grows_past_its_initial_capacity() {
+    let mut fs = FileSystem::with_capacity(3);
+
+    for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+        fs.create_file(name.to_string(), name.to_string()).unwrap();
+    }
+
+    assert_eq!(fs.file_count(), 4);
+    assert_eq!(fs.get_file("d.txt").unwrap().id, 4);
+    assert_eq!(fs.read_file("a.txt").unwrap(), "a.txt");
+    assert!(fs.verify_index_integrity());
+}
+
+#[test]
+fn compact_closes_the_gap_left_by_a_deleted_file() {
+    let mut fs = fs_with(&[("a.txt", "a"), ("b.txt", "b"), ("c.txt", "c")]);
+    fs.delete_file("b.txt").unwrap();
+
+    let mapping = fs.compact().unwrap();
+
+    assert_eq!(mapping, HashMap::from([(1, 1), (3, 2)]));
+    assert_eq!(fs.get_file("c.txt").unwrap().id, 2);
+    assert_eq!(fs.get_file_by_id(2).unwrap().name, "c.txt");
+    assert_eq!(fs.create_file("d.txt".to_string(), String::new()).unwrap(), 3);
+    assert!(fs.verify_index_integrity());
+}
+
+#[test]
+fn extension_index_follows_create_rename_and_delete() {
+    let mut fs = fs_with(&[("a.txt", ""), ("b.md", ""), ("c.txt", "")]);
+    let names = |files: Vec<&File>| files.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(fs.filter_by_extension("txt")), ["a.txt", "c.txt"]);
+    assert_eq!(names(fs.filter_by_extension(".md")), ["b.md"]);
+
+    fs.rename_file("a.txt", "a.md").unwrap();
+    assert_eq!(names(fs.filter_by_extension("txt")), ["c.txt"]);
+    assert_eq!(names(fs.filter_by_extension("md")), ["a.md", "b.md"]);
+
+    fs.delete_file("b.md").unwrap();
+    fs.create_file("d.txt".to_string(), String::new()).unwrap();
+    assert_eq!(names(fs.filter_by_extension("md")), ["a.md"]);
+    assert_eq!(names(fs.filter_by_extension("txt")), ["c.txt", "d.txt"]);
+    assert!(fs.filter_by_extension("rs").is_empty());
+}
+
+#[test]
+fn set_operations_compare_paths() {
+    let left = fs_with(&[("a.txt", "1"), ("b.txt", "2"), ("c.txt", "3")]);
+    let right = fs_with(&[("b.txt", "other"), ("c.txt", "3"), ("d.txt", "4")]);
+
+    let names = |files: Vec<&File>| files.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(left.difference(&right)), ["a.txt"]);
+    assert_eq!(names(left.intersection(&right)), ["b.txt", "c.txt"]);
+    assert_eq!(names(left.symmetric_difference(&right)), ["a.txt", "d.txt"]);
+}
+
+#[test]
+fn set_operations_on_disjoint_filesystems() {
+    let left = fs_with(&[("a.txt", "")]);
+    let right = fs_with(&[("b.txt", "")]);
+
+    let names = |files: Vec<&File>| files.iter().map(|f| f.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(left.difference(&right)), ["a.txt"]);
+    assert!(left.intersection(&right).is_empty());
+    assert_eq!(names(left.symmetric_difference(&right)), ["a.txt", "b.txt"]);
+}
+
+#[test]
+fn partition_splits_by_extension_and_renumbers() {
+    let mut fs = fs_with(&[("a.txt", "a"), ("b.md", "b"), ("c.txt", "c")]);
+    fs.create_binary_file("d.bin".to_string(), vec![0, 1]).unwrap();
+
+    let (txt, rest) = fs.partition(|f| f.extension() == Some("txt"));
+
+    let names = |fs: &FileSystem| fs.iter().map(|f| (f.id, f.name.clone())).collect::<Vec<_>>();
+    assert_eq!(names(&txt), [(1, "a.txt".to_string()), (2, "c.txt".to_string())]);
+    assert_eq!(names(&rest), [(1, "b.md".to_string())]);
+    assert_eq!(txt.file_count(), 2);
+    assert_eq!(rest.file_count(), 2);
+    assert_eq!(rest.read_binary_file("d.bin").unwrap(), [0, 1]);
+    assert_eq!(rest.path_of(2).unwrap(), "/d.bin");
+    assert!(txt.verify_index_integrity() && rest.verify_index_integrity());
+}
+
+#[test]
+fn partition_where_all_or_none_match() {
+    let (all, none) = fs_with(&[("a.txt", ""), ("b.txt", "")]).partition(|_| true);
+    assert_eq!((all.file_count(), none.file_count()), (2, 0));
+
+    let (none, all) = fs_with(&[("a.txt", ""), ("b.txt", "")]).partition(|_| false);
+    assert_eq!((none.file_count(), all.file_count()), (0, 2));
+}
+
+#[test]
+fn extend_skips_files_whose_names_collide() {
+    let source = fs_with(&[("a.txt", "new"), ("b.txt", "b")]);
+    let mut fs = fs_with(&[("a.txt", "old")]);
+
+    fs.extend(source);
+
+    assert_eq!(fs.file_count(), 2);
+    assert_eq!(fs.read_file("a.txt").unwrap(), "old");
+    assert_eq!(fs.get_file("b.txt").unwrap().id, 2);
+}
+
+#[test]
+fn collecting_files_keeps_the_first_of_each_name() {
+    let first = fs_with(&[("a.txt", "first"), ("b.txt", "b")]);
+    let second = fs_with(&[("a.txt", "second"), ("c.txt", "c")]);
+
+    let mut fs: FileSystem = first.into_iter().chain(second).collect();
+
+    assert_eq!(fs.file_count(), 3);
+    assert_eq!(fs.read_file("a.txt").unwrap(), "first");
+    assert_eq!(fs.get_file("c.txt").unwrap().id, 3);
+}
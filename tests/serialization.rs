@@ -0,0 +1,219 @@
+use cli::file::{FilePermissions, FileSystem, SchemaVersion};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[test]
+fn snapshot_round_trips_every_field() {
+    let mut fs = FileSystem::new();
+    fs.mkdir("docs").unwrap();
+    fs.mkdir("docs/old").unwrap();
+    fs.create_file("gone.txt".to_string(), String::new()).unwrap();
+    let id = fs.create_file_owned("docs/a.txt".to_string(), "first".to_string(), "alice".to_string()).unwrap();
+    fs.write_file("docs/a.txt", "second".to_string()).unwrap();
+    fs.add_tag("docs/a.txt", "draft").unwrap();
+    fs.set_permissions("docs/a.txt", FilePermissions { read: true, write: false, execute: true }).unwrap();
+    fs.create_symlink("docs/old/link".to_string(), "../a.txt").unwrap();
+    fs.create_binary_file("docs/logo.bin".to_string(), vec![0, 159, 255]).unwrap();
+    fs.delete_file("gone.txt").unwrap();
+    {
+        let file = fs.get_file_by_id_mut(id).unwrap();
+        file.max_versions = 3;
+        file.created_at = UNIX_EPOCH + Duration::new(1_000, 5);
+        file.accessed_at = UNIX_EPOCH + Duration::new(3_000, 7);
+    }
+    fs.lock_file("docs/a.txt").unwrap();
+    fs.cd("docs").unwrap();
+
+    let mut restored = FileSystem::restore(&fs.snapshot()).unwrap();
+
+    let (before, after) = (fs.get_file_by_id(id).unwrap(), restored.get_file_by_id(id).unwrap());
+    assert_eq!(after.name, "a.txt");
+    assert_eq!(*after.content, "second");
+    assert_eq!(after.created_at, before.created_at);
+    assert_eq!(after.modified_at, before.modified_at);
+    assert_eq!(after.accessed_at, before.accessed_at);
+    assert_eq!(after.tags, vec!["draft".to_string()]);
+    assert!(after.locked);
+    assert_eq!(after.permissions, before.permissions);
+    assert_eq!(after.owner, "alice");
+    assert_eq!(after.versions, before.versions);
+    assert_eq!(after.versions[0].0, "first");
+    assert_eq!(after.max_versions, 3);
+
+    assert_eq!(restored.pwd(), "/docs");
+    assert_eq!(restored.directory_of(id), "/docs");
+    assert!(restored.directory("/docs/old").is_ok());
+    let link = restored.get_file("old/link").unwrap();
+    assert!(link.is_symlink);
+    assert_eq!(link.symlink_target.as_deref(), Some("../a.txt"));
+    assert_eq!(restored.read_file("old/link").unwrap(), "second");
+    assert_eq!(restored.read_binary_file("logo.bin").unwrap(), &[0, 159, 255]);
+
+    let next = restored.create_file("new.txt".to_string(), String::new()).unwrap();
+    assert_eq!(next, 5);
+    assert!(restored.verify_index_integrity());
+}
+
+#[test]
+fn restore_reads_legacy_snapshots() {
+    let mut bytes = b"FSNP".to_vec();
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    for text in ["a.txt", "hello"] {
+        bytes.extend_from_slice(&(text.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(text.as_bytes());
+    }
+    bytes.extend_from_slice(&7u32.to_le_bytes());
+    bytes.extend_from_slice(&60u64.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+
+    let mut fs = FileSystem::restore(&bytes).unwrap();
+
+    let file = fs.get_file_by_id(7).unwrap();
+    assert_eq!(file.modified_at, UNIX_EPOCH + Duration::from_secs(60));
+    assert_eq!(fs.read_file("/a.txt").unwrap(), "hello");
+    assert_eq!(fs.create_file("b.txt".to_string(), String::new()).unwrap(), 8);
+}
+
+#[test]
+fn restore_rejects_trailing_data() {
+    let mut bytes = FileSystem::new().snapshot();
+    bytes.push(0);
+    assert!(FileSystem::restore(&bytes).is_err());
+}
+
+#[test]
+fn json_round_trips_tags_symlinks_and_modified_at() {
+    let mut fs = FileSystem::new();
+    fs.create_file("a.txt".to_string(), "hello \"world\"".to_string()).unwrap();
+    fs.add_tag("a.txt", "draft").unwrap();
+    fs.add_tag("a.txt", "todo").unwrap();
+    fs.get_file_mut("a.txt").unwrap().modified_at = UNIX_EPOCH + Duration::from_secs(5_000);
+    fs.create_symlink("link".to_string(), "a.txt").unwrap();
+
+    let mut imported = FileSystem::import_from_json(&fs.export_to_json()).unwrap();
+
+    let file = imported.get_file("a.txt").unwrap();
+    assert_eq!(*file.content, "hello \"world\"");
+    assert_eq!(file.tags, vec!["draft".to_string(), "todo".to_string()]);
+    assert_eq!(file.modified_at, UNIX_EPOCH + Duration::from_secs(5_000));
+    let link = imported.get_file("link").unwrap();
+    assert!(link.is_symlink);
+    assert_eq!(link.symlink_target.as_deref(), Some("a.txt"));
+    assert_eq!(imported.read_file("link").unwrap(), "hello \"world\"");
+}
+
+#[test]
+fn json_round_trips_nested_directories() {
+    let mut fs = FileSystem::new();
+    fs.mkdir("docs").unwrap();
+    fs.mkdir("docs/empty").unwrap();
+    fs.mkdir("notes").unwrap();
+    fs.create_file("docs/a.txt".to_string(), "in docs".to_string()).unwrap();
+    fs.create_file("notes/a.txt".to_string(), "in notes".to_string()).unwrap();
+    fs.create_symlink("docs/link".to_string(), "../notes/a.txt").unwrap();
+    fs.create_binary_file("docs/logo.bin".to_string(), vec![0, 255]).unwrap();
+
+    let json = fs.export_to_json();
+    let mut imported = FileSystem::import_from_json(&json).unwrap();
+
+    assert_eq!(imported.read_file("docs/a.txt").unwrap(), "in docs");
+    assert_eq!(imported.read_file("notes/a.txt").unwrap(), "in notes");
+    assert_eq!(imported.read_file("docs/link").unwrap(), "in notes");
+    assert!(imported.cd("docs/empty").is_ok());
+    assert!(!json.contains("logo.bin"));
+    assert_eq!(imported.file_count(), 3);
+}
+
+#[test]
+fn migrate_upgrades_v1_state_without_losing_data() {
+    let v1 = r#"[{"id":4,"name":"a.txt","content":"hello","size":5,"created_at":1000},{"id":9,"name":"b.txt","content":"","size":0,"created_at":2000}]"#;
+    let path = std::env::temp_dir().join(format!("cli_migrate_{}.json", std::process::id()));
+    std::fs::write(&path, v1).unwrap();
+
+    let fs = FileSystem::migrate(&path);
+    std::fs::remove_file(&path).unwrap();
+    let mut fs = fs.unwrap();
+
+    let a = fs.get_file_by_id(4).unwrap();
+    assert_eq!(a.name, "a.txt");
+    assert_eq!(*a.content, "hello");
+    assert_eq!(a.size, 5);
+    assert_eq!(a.created_at, UNIX_EPOCH + Duration::from_secs(1000));
+    assert_eq!(a.modified_at, a.created_at);
+    assert!(a.tags.is_empty());
+    assert!(!a.is_symlink);
+    assert_eq!(fs.get_file_by_id(9).unwrap().modified_at, UNIX_EPOCH + Duration::from_secs(2000));
+    assert_eq!(fs.create_file("c.txt".to_string(), String::new()).unwrap(), 10);
+
+    let v2 = fs.export_to_json();
+    assert!(v2.starts_with(r#"{"version":2,"files":["#));
+    assert_eq!(FileSystem::import_from_json(&v2).unwrap().file_count(), 3);
+}
+
+#[test]
+fn import_rejects_unknown_schema_versions() {
+    assert!(FileSystem::import_from_json(r#"{"version":3,"files":[]}"#).is_err());
+    assert_eq!(SchemaVersion::from_number(1).unwrap(), SchemaVersion::V1);
+    assert_eq!(SchemaVersion::CURRENT.number(), 2);
+}
+
+#[test]
+fn import_from_disk_returns_skipped_subdirectories() {
+    let dir = std::env::temp_dir().join(format!("cli_import_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("nested")).unwrap();
+    std::fs::write(dir.join("a.txt"), "hello").unwrap();
+    std::fs::write(dir.join("b.bin"), [0xff, 0x00]).unwrap();
+
+    let result = FileSystem::import_from_disk(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let (mut fs, skipped) = result.unwrap();
+
+    assert_eq!(skipped, vec!["nested".to_string()]);
+    assert_eq!(fs.read_file("a.txt").unwrap(), "hello");
+    assert_eq!(fs.read_binary_file("b.bin").unwrap(), &[0xff, 0x00]);
+}
+
+#[test]
+fn rss_round_trips_whitespace_and_long_content() {
+    let long = "x".repeat(250);
+    let mut fs = FileSystem::new();
+    fs.create_file("padded.txt".to_string(), "  indented\n\ttabbed  \n".to_string()).unwrap();
+    fs.create_file("long.txt".to_string(), long.clone()).unwrap();
+    fs.create_file("markup.txt".to_string(), "<b>&amp;</b>".to_string()).unwrap();
+
+    let xml = fs.export_rss("Feed", "http://example.com", "files");
+    assert!(xml.contains(&format!("<description>{}</description>", "x".repeat(200))));
+
+    let mut imported = FileSystem::new();
+    assert_eq!(imported.import_rss(&xml).unwrap(), 3);
+    assert_eq!(imported.read_file("padded.txt").unwrap(), "  indented\n\ttabbed  \n");
+    assert_eq!(imported.read_file("long.txt").unwrap(), long);
+    assert_eq!(imported.read_file("markup.txt").unwrap(), "<b>&amp;</b>");
+}
+
+#[test]
+fn rss_export_is_well_formed_with_a_channel_header_and_every_item() {
+    let mut fs = FileSystem::new();
+    fs.create_file("a.txt".to_string(), "first".to_string()).unwrap();
+    fs.create_file("b & c.txt".to_string(), "x < y".to_string()).unwrap();
+    fs.create_file("d.txt".to_string(), String::new()).unwrap();
+
+    let xml = fs.export_rss("My <Feed>", "http://example.com/?a=1&b=2", "All files");
+
+    assert!(xml.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\""));
+    assert!(xml.ends_with("</channel>\n</rss>\n"));
+    for tag in ["rss", "channel", "item", "title", "link", "description", "content:encoded", "pubDate", "guid"] {
+        let opened = xml.matches(&format!("<{}>", tag)).count() + xml.matches(&format!("<{} ", tag)).count();
+        assert_eq!(opened, xml.matches(&format!("</{}>", tag)).count(), "unbalanced <{}>", tag);
+    }
+
+    let channel = &xml[..xml.find("<item>").unwrap()];
+    assert!(channel.contains("<title>My &lt;Feed&gt;</title>"));
+    assert!(channel.contains("<link>http://example.com/?a=1&amp;b=2</link>"));
+    assert!(channel.contains("<description>All files</description>"));
+
+    assert_eq!(xml.matches("<item>").count(), 3);
+    for name in ["a.txt", "b &amp; c.txt", "d.txt"] {
+        assert!(xml.contains(&format!("<title>{}</title>", name)), "missing item {}", name);
+    }
+    assert!(xml.contains("<content:encoded>x &lt; y</content:encoded>"));
+}